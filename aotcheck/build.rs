@@ -0,0 +1,35 @@
+use std::path::Path;
+
+/// Images to compile: puzzle inputs from the days, and small programs
+/// written to hit particular corners of the compiler.
+const IMAGES: &[(&str, &str)] = &[
+    (
+        "quine",
+        "109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99",
+    ),
+    ("boost", "../day9/src/input.txt"),
+    ("drone", "../day19/src/input.txt"),
+    ("nic", "../day23/src/input"),
+    // writes its input over the operand of `out #5` through the relative
+    // base, which only the interpreter can follow.
+    ("patch", "109,7,203,1,1105,1,7,104,5,99"),
+    // adds 1 to isize::MAX.
+    ("overflow", "1101,9223372036854775807,1,0,99"),
+];
+
+fn main() {
+    let mut out = String::new();
+    for &(name, image) in IMAGES {
+        let src = if image.ends_with(".txt") || image.ends_with("input") {
+            println!("cargo:rerun-if-changed={}", image);
+            std::fs::read_to_string(image).unwrap()
+        } else {
+            image.to_string()
+        };
+        let image = intcode::parse_program(&src).unwrap();
+        out += &intcode::aot::compile(&image, name);
+    }
+    let path = Path::new(&std::env::var("OUT_DIR").unwrap()).join("images.rs");
+    std::fs::write(path, out).unwrap();
+    println!("cargo:rerun-if-changed=build.rs");
+}
@@ -0,0 +1,131 @@
+//! Runs Intcode images compiled by `intcode::aot` next to the interpreter,
+//! checking they agree at every input, output and halt.
+
+use intcode::aot::{Compiled, Program};
+use intcode::{IntComp, IntcodeError, State};
+
+include!(concat!(env!("OUT_DIR"), "/images.rs"));
+
+/// Steps the interpreter until it outputs, needs input, halts or faults,
+/// like `run_until_io` does.
+fn interpret_until_io(comp: &mut IntComp) -> Result<State, IntcodeError> {
+    loop {
+        let outputs = comp.output.len();
+        match comp.step()? {
+            State::Run if comp.output.len() == outputs => (),
+            state => return Ok(state),
+        }
+    }
+}
+
+/// How a lockstep run ended.
+#[derive(Debug)]
+pub struct Run {
+    pub output: Vec<isize>,
+    pub result: Result<State, IntcodeError>,
+    /// Instructions the compiled side ran in the interpreter.
+    pub interpreted: u64,
+}
+
+/// Runs `program` compiled and interpreted side by side, giving both the
+/// next of `input` whenever they ask, until they halt, fault, run out of
+/// input or have stopped `limit` times. Panics where they disagree.
+pub fn lockstep(
+    program: &'static Program,
+    input: impl IntoIterator<Item = isize>,
+    limit: usize,
+) -> Run {
+    let mut input = input.into_iter();
+    let mut compiled = Compiled::new(program, IntComp::new(program.image.to_vec(), &[]));
+    let mut interp = IntComp::new(program.image.to_vec(), &[]);
+    let mut result = Ok(State::Run);
+    for stop in 0..limit {
+        result = compiled.run_until_io();
+        let expected = interpret_until_io(&mut interp);
+        assert_eq!(result, expected, "result at stop {}", stop);
+        assert_eq!(compiled.output, interp.output, "output at stop {}", stop);
+        assert_eq!(compiled.pc, interp.pc, "pc at stop {}", stop);
+        assert_eq!(
+            compiled.rel_base, interp.rel_base,
+            "rel_base at stop {}",
+            stop
+        );
+        match result {
+            Ok(State::Run) => (),
+            Ok(State::NeedsInput) => match input.next() {
+                Some(val) => {
+                    compiled.input_mut().push_back(val);
+                    interp.input.push_back(val);
+                }
+                None => break,
+            },
+            _ => break,
+        }
+    }
+    assert_eq!(compiled.mem.as_slice(), interp.mem.as_slice(), "memory");
+    Run {
+        output: compiled.output.iter().copied().collect(),
+        result,
+        interpreted: compiled.interpreted(),
+    }
+}
+
+#[test]
+fn test_quine() {
+    let run = lockstep(&quine::PROGRAM, vec![], 100);
+    assert_eq!(run.result, Ok(State::Halt));
+    assert_eq!(run.output, quine::IMAGE.to_vec());
+    assert_eq!(run.interpreted, 0);
+}
+
+#[test]
+fn test_boost() {
+    for &mode in &[1, 2] {
+        let run = lockstep(&boost::PROGRAM, vec![mode], 10);
+        assert_eq!(run.result, Ok(State::Halt));
+        assert_eq!(
+            run.output.len(),
+            1,
+            "BOOST reported faults: {:?}",
+            run.output
+        );
+        assert_eq!(run.interpreted, 0);
+    }
+}
+
+#[test]
+fn test_drone() {
+    for &(x, y) in &[(0, 0), (3, 4), (10, 12), (49, 49), (600, 800), (-1, 5)] {
+        let run = lockstep(&drone::PROGRAM, vec![x, y], 10);
+        assert_eq!(run.output.len(), 1, "drone at {},{}", x, y);
+    }
+}
+
+#[test]
+fn test_nic() {
+    let mut packets = 0;
+    for addr in 0..50 {
+        let input = std::iter::once(addr).chain(std::iter::repeat_n(-1, 50));
+        let run = lockstep(&nic::PROGRAM, input, 1000);
+        assert_eq!(run.result, Ok(State::NeedsInput));
+        packets += run.output.len() / 3;
+    }
+    assert!(packets > 0);
+}
+
+#[test]
+fn test_patch() {
+    let run = lockstep(&patch::PROGRAM, vec![42], 10);
+    assert_eq!(run.result, Ok(State::Halt));
+    assert_eq!(run.output, vec![42]);
+    assert!(run.interpreted > 0);
+}
+
+#[test]
+fn test_overflow() {
+    let run = lockstep(&overflow::PROGRAM, vec![], 10);
+    assert_eq!(
+        run.result,
+        Err(IntcodeError::Overflow { pc: 0, word: 1101 })
+    );
+}
@@ -0,0 +1,78 @@
+//! Reading the capital letters that turn up drawn in pixels: each is 4
+//! pixels wide and 6 tall, with a blank column after it.
+
+const WIDTH: usize = 4;
+const HEIGHT: usize = 6;
+
+const GLYPHS: &[(char, [&str; HEIGHT])] = &[
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+/// Reads the letters in `text`, where `█` or `#` is a lit pixel and anything
+/// else is dark. The letters start at the first lit row and column, and any
+/// that aren't recognized read as `?`.
+pub fn read(text: &str) -> String {
+    let rows: Vec<Vec<bool>> = text
+        .lines()
+        .map(|line| line.chars().map(|c| c == '█' || c == '#').collect())
+        .collect();
+    let top = match rows.iter().position(|row| row.contains(&true)) {
+        Some(top) => top,
+        None => return String::new(),
+    };
+    let rows = &rows[top..rows.len().min(top + HEIGHT)];
+    let lit = |y: usize, x: usize| rows.get(y).and_then(|row| row.get(x)) == Some(&true);
+    let left = rows
+        .iter()
+        .filter_map(|row| row.iter().position(|&p| p))
+        .min()
+        .unwrap_or(0);
+    let right = rows
+        .iter()
+        .filter_map(|row| row.iter().rposition(|&p| p))
+        .max()
+        .unwrap_or(0);
+
+    (left..=right)
+        .step_by(WIDTH + 1)
+        .map(|x| {
+            let glyph = GLYPHS.iter().find(|(_, pattern)| {
+                pattern.iter().enumerate().all(|(y, line)| {
+                    line.chars()
+                        .enumerate()
+                        .all(|(dx, c)| (c == '#') == lit(y, x + dx))
+                })
+            });
+            glyph.map_or('?', |&(letter, _)| letter)
+        })
+        .collect()
+}
+
+#[test]
+fn test_read() {
+    let text = "\n\
+                 #..#...##\n\
+                 #..#....#\n\
+                 ####....#\n\
+                 #..#....#\n\
+                 #..#.#..#\n\
+                 #..#..##.\n";
+    assert_eq!(read(text), "HJ");
+    assert_eq!(read(&text.replace("####", "###.")), "?J");
+    assert_eq!(read("..."), "");
+}
@@ -0,0 +1,301 @@
+//! The hull painting robot: it reads the color of the panel it's over, then
+//! says what to paint it and which way to turn, before moving on a panel.
+
+use intcode::{IntComp, IntcodeError, State};
+use std::collections::HashMap;
+use std::fmt::{self, Write};
+
+pub type Pos = (isize, isize);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Color {
+    Black = 0,
+    White = 1,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Dir {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Dir {
+    /// 0 turns left and 1 right.
+    fn turn(self, turn: isize) -> Option<Dir> {
+        match (self, turn) {
+            (Dir::Up, 0) | (Dir::Down, 1) => Some(Dir::Left),
+            (Dir::Left, 0) | (Dir::Right, 1) => Some(Dir::Down),
+            (Dir::Down, 0) | (Dir::Up, 1) => Some(Dir::Right),
+            (Dir::Right, 0) | (Dir::Left, 1) => Some(Dir::Up),
+            _ => None,
+        }
+    }
+
+    fn step(self, pos: Pos) -> Pos {
+        match self {
+            Dir::Up => (pos.0, pos.1 - 1),
+            Dir::Down => (pos.0, pos.1 + 1),
+            Dir::Left => (pos.0 - 1, pos.1),
+            Dir::Right => (pos.0 + 1, pos.1),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PainterError {
+    Intcode(IntcodeError),
+    BadColor {
+        color: isize,
+    },
+    BadTurn {
+        turn: isize,
+    },
+    /// The program halted after a color, without saying which way to turn.
+    Truncated {
+        values: usize,
+    },
+}
+
+impl fmt::Display for PainterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PainterError::Intcode(e) => write!(f, "{}", e),
+            PainterError::BadColor { color } => write!(f, "invalid color {}", color),
+            PainterError::BadTurn { turn } => write!(f, "invalid turn direction {}", turn),
+            PainterError::Truncated { values } => {
+                write!(f, "halted with {} values of a paint job sent", values)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PainterError {}
+
+impl From<IntcodeError> for PainterError {
+    fn from(e: IntcodeError) -> PainterError {
+        PainterError::Intcode(e)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Panel {
+    pub color: Color,
+    /// Each coat of paint and when it went on, counting paint jobs from 0.
+    pub coats: Vec<(usize, Color)>,
+}
+
+pub struct HullPainter {
+    comp: IntComp,
+    pos: Pos,
+    dir: Dir,
+    panels: HashMap<Pos, Panel>,
+    /// How many paint jobs have been done.
+    jobs: usize,
+}
+
+impl HullPainter {
+    /// The robot starts at (0, 0) facing up, with every panel black.
+    pub fn new(mem: Vec<isize>) -> HullPainter {
+        HullPainter {
+            comp: IntComp::new(mem, &[]),
+            pos: (0, 0),
+            dir: Dir::Up,
+            panels: HashMap::new(),
+            jobs: 0,
+        }
+    }
+
+    /// Colors `pos` before the robot starts, which doesn't count as painting.
+    pub fn set_start(&mut self, pos: Pos, color: Color) {
+        self.panels.insert(
+            pos,
+            Panel {
+                color,
+                coats: Vec::new(),
+            },
+        );
+    }
+
+    pub fn color(&self, pos: Pos) -> Color {
+        self.panels
+            .get(&pos)
+            .map_or(Color::Black, |panel| panel.color)
+    }
+
+    pub fn panel(&self, pos: Pos) -> Option<&Panel> {
+        self.panels.get(&pos)
+    }
+
+    /// How many panels got at least one coat of paint.
+    pub fn painted(&self) -> usize {
+        self.panels
+            .values()
+            .filter(|panel| !panel.coats.is_empty())
+            .count()
+    }
+
+    /// Runs the robot until it's done, telling `on_paint` about each coat
+    /// of paint as it goes on, and `on_input` each time the robot looks at a
+    /// panel.
+    pub fn run(
+        &mut self,
+        mut on_paint: impl FnMut(Pos, Color),
+        mut on_input: impl FnMut(),
+    ) -> Result<(), PainterError> {
+        loop {
+            match self.comp.run_until_io()? {
+                State::Run if self.comp.output.len() == 2 => {
+                    let color = match self.comp.output[0] {
+                        0 => Color::Black,
+                        1 => Color::White,
+                        color => return Err(PainterError::BadColor { color }),
+                    };
+                    let turn = self.comp.output[1];
+                    let dir = self.dir.turn(turn).ok_or(PainterError::BadTurn { turn })?;
+                    self.comp.output.clear();
+                    self.paint(color);
+                    on_paint(self.pos, color);
+                    self.dir = dir;
+                    self.pos = self.dir.step(self.pos);
+                }
+                State::Run => (),
+                State::NeedsInput => {
+                    on_input();
+                    let color = self.color(self.pos);
+                    self.comp.input.push_back(color as isize);
+                }
+                State::Halt if !self.comp.output.is_empty() => {
+                    let values = self.comp.output.len();
+                    return Err(PainterError::Truncated { values });
+                }
+                State::Halt => return Ok(()),
+            }
+        }
+    }
+
+    fn paint(&mut self, color: Color) {
+        let job = self.jobs;
+        self.jobs += 1;
+        let panel = self.panels.entry(self.pos).or_insert(Panel {
+            color,
+            coats: Vec::new(),
+        });
+        panel.color = color;
+        panel.coats.push((job, color));
+    }
+
+    /// The corners of the panels painted or given a starting color.
+    fn bounds(&self) -> (Pos, Pos) {
+        let mut positions = self.panels.keys();
+        let first = positions.next().copied().unwrap_or((0, 0));
+        positions.fold((first, first), |(min, max), &(x, y)| {
+            ((min.0.min(x), min.1.min(y)), (max.0.max(x), max.1.max(y)))
+        })
+    }
+
+    /// Calls `pixel` for each panel in the bounds, row by row, then once
+    /// with `None` at the end of each row.
+    fn scan(&self, mut pixel: impl FnMut(Option<Pos>)) {
+        let (min, max) = self.bounds();
+        for y in min.1..=max.1 {
+            for x in min.0..=max.0 {
+                pixel(Some((x, y)));
+            }
+            pixel(None);
+        }
+    }
+
+    fn size(&self) -> (isize, isize) {
+        let (min, max) = self.bounds();
+        (max.0 - min.0 + 1, max.1 - min.1 + 1)
+    }
+
+    /// White panels as `█` and black ones as spaces, a line per row.
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        self.scan(|pos| match pos {
+            Some(pos) if self.color(pos) == Color::White => text.push('█'),
+            Some(_) => text.push(' '),
+            None => text.push('\n'),
+        });
+        text
+    }
+
+    /// A plain PBM bitmap, black panels black and white ones white.
+    pub fn to_pbm(&self) -> String {
+        let (width, height) = self.size();
+        let mut pbm = format!("P1\n{} {}\n", width, height);
+        self.scan(|pos| match pos {
+            Some(pos) if self.color(pos) == Color::White => pbm.push_str("0 "),
+            Some(_) => pbm.push_str("1 "),
+            None => pbm.push('\n'),
+        });
+        pbm
+    }
+
+    /// A plain PGM graymap of how many coats each panel got, the most
+    /// painted ones white.
+    pub fn to_pgm(&self) -> String {
+        let (width, height) = self.size();
+        let coats = |pos| self.panel(pos).map_or(0, |panel| panel.coats.len());
+        let most = self.panels.values().map(|p| p.coats.len()).max();
+        let mut pgm = format!("P2\n{} {}\n{}\n", width, height, most.unwrap_or(0).max(1));
+        self.scan(|pos| match pos {
+            Some(pos) => write!(pgm, "{} ", coats(pos)).unwrap(),
+            None => pgm.push('\n'),
+        });
+        pgm
+    }
+}
+
+#[test]
+fn test_painter() {
+    // paints white and turns left four times, going round a square, then
+    // paints the first panel black again.
+    let mut mem = Vec::new();
+    for &(color, turn) in &[(1, 0), (1, 0), (1, 0), (1, 0), (0, 1)] {
+        mem.extend_from_slice(&[3, 100, 104, color, 104, turn]);
+    }
+    mem.push(99);
+    let mut painter = HullPainter::new(mem);
+    painter.set_start((1, 0), Color::White);
+    let mut jobs = 0;
+    painter.run(|_, _| jobs += 1, || ()).unwrap();
+
+    assert_eq!(jobs, 5);
+    assert_eq!(painter.painted(), 4);
+    assert_eq!(
+        painter.panel((0, 1)).unwrap().coats,
+        vec![(3, Color::White)]
+    );
+    assert_eq!(
+        painter.panel((0, 0)).unwrap().coats,
+        vec![(0, Color::White), (4, Color::Black)]
+    );
+    assert_eq!(painter.to_text(), "█ █\n██ \n");
+    assert_eq!(painter.to_pbm(), "P1\n3 2\n0 1 0 \n0 0 1 \n");
+    assert_eq!(painter.to_pgm(), "P2\n3 2\n2\n1 2 0 \n1 1 0 \n");
+}
+
+#[test]
+fn test_painter_errors() {
+    let run = |mem: Vec<isize>| HullPainter::new(mem).run(|_, _| (), || ());
+    assert_eq!(
+        run(vec![3, 100, 104, 5, 104, 0, 99]),
+        Err(PainterError::BadColor { color: 5 })
+    );
+    assert_eq!(
+        run(vec![3, 100, 104, 1, 104, 2, 99]),
+        Err(PainterError::BadTurn { turn: 2 })
+    );
+    assert_eq!(
+        run(vec![3, 100, 104, 1, 99]),
+        Err(PainterError::Truncated { values: 1 })
+    );
+    assert!(matches!(
+        run(vec![3, 100, 98]),
+        Err(PainterError::Intcode(..))
+    ));
+}
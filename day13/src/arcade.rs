@@ -0,0 +1,217 @@
+//! The arcade cabinet's output protocol: the program sends `(x, y, id)`
+//! triples, each drawing a tile, except that `(-1, 0, score)` sets the score.
+
+use intcode::{IntComp, IntcodeError, State};
+use std::collections::HashMap;
+use std::fmt;
+
+pub type Pos = (isize, isize);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Tile {
+    Empty,
+    Wall,
+    Block,
+    Paddle,
+    Ball,
+}
+
+impl Tile {
+    fn from_id(id: isize) -> Option<Tile> {
+        match id {
+            0 => Some(Tile::Empty),
+            1 => Some(Tile::Wall),
+            2 => Some(Tile::Block),
+            3 => Some(Tile::Paddle),
+            4 => Some(Tile::Ball),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event {
+    DrawTile { pos: Pos, tile: Tile },
+    Score(isize),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ArcadeError {
+    Intcode(IntcodeError),
+    UnknownTile {
+        pos: Pos,
+        id: isize,
+    },
+    OffBoard {
+        pos: Pos,
+    },
+    /// The program halted part way through a triple.
+    Truncated {
+        values: usize,
+    },
+}
+
+impl fmt::Display for ArcadeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ArcadeError::Intcode(e) => write!(f, "{}", e),
+            ArcadeError::UnknownTile { pos, id } => {
+                write!(f, "unknown tile id {} at {:?}", id, pos)
+            }
+            ArcadeError::OffBoard { pos } => write!(f, "tile drawn off the board at {:?}", pos),
+            ArcadeError::Truncated { values } => {
+                write!(f, "halted with {} values of a triple sent", values)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ArcadeError {}
+
+impl From<IntcodeError> for ArcadeError {
+    fn from(e: IntcodeError) -> ArcadeError {
+        ArcadeError::Intcode(e)
+    }
+}
+
+fn decode(x: isize, y: isize, val: isize) -> Result<Event, ArcadeError> {
+    let pos = (x, y);
+    match pos {
+        (-1, 0) => Ok(Event::Score(val)),
+        _ if x < 0 || y < 0 => Err(ArcadeError::OffBoard { pos }),
+        _ => match Tile::from_id(val) {
+            Some(tile) => Ok(Event::DrawTile { pos, tile }),
+            None => Err(ArcadeError::UnknownTile { pos, id: val }),
+        },
+    }
+}
+
+/// The cabinet and what's on its screen.
+#[derive(Clone)]
+pub struct Arcade {
+    pub comp: IntComp,
+    board: HashMap<Pos, Tile>,
+    ball: Option<Pos>,
+    paddle: Option<Pos>,
+    score: isize,
+}
+
+impl Arcade {
+    /// With `free_play`, the game runs without quarters, as part 2 wants.
+    pub fn new(mut mem: Vec<isize>, free_play: bool) -> Arcade {
+        // an empty image has nowhere to put quarters, and faults on its own.
+        if let (true, Some(quarters)) = (free_play, mem.first_mut()) {
+            *quarters = 2;
+        }
+        Arcade {
+            comp: IntComp::new(mem, &[]),
+            board: HashMap::new(),
+            ball: None,
+            paddle: None,
+            score: 0,
+        }
+    }
+
+    /// Runs until the joystick is read or the game ends.
+    pub fn run(&mut self) -> Result<State, ArcadeError> {
+        self.run_with(|_| ())
+    }
+
+    /// Like `run`, passing each event to `on_event` once it's applied.
+    pub fn run_with(&mut self, mut on_event: impl FnMut(&Event)) -> Result<State, ArcadeError> {
+        loop {
+            let state = self.comp.run_until_io()?;
+            while self.comp.output.len() >= 3 {
+                let triple: Vec<_> = self.comp.output.drain(..3).collect();
+                let event = decode(triple[0], triple[1], triple[2])?;
+                self.apply(&event);
+                on_event(&event);
+            }
+            match state {
+                State::Run => (),
+                State::Halt if !self.comp.output.is_empty() => {
+                    let values = self.comp.output.len();
+                    return Err(ArcadeError::Truncated { values });
+                }
+                _ => return Ok(state),
+            }
+        }
+    }
+
+    fn apply(&mut self, event: &Event) {
+        match *event {
+            Event::Score(score) => self.score = score,
+            Event::DrawTile { pos, tile } => {
+                match tile {
+                    Tile::Ball => self.ball = Some(pos),
+                    Tile::Paddle => self.paddle = Some(pos),
+                    _ if self.ball == Some(pos) => self.ball = None,
+                    _ if self.paddle == Some(pos) => self.paddle = None,
+                    _ => (),
+                }
+                self.board.insert(pos, tile);
+            }
+        }
+    }
+
+    /// -1 tilts it left, 1 right, and 0 leaves it in the middle.
+    pub fn joystick(&mut self, dir: isize) {
+        self.comp.input.push_back(dir);
+    }
+
+    pub fn blocks(&self) -> usize {
+        self.board
+            .values()
+            .filter(|&&tile| tile == Tile::Block)
+            .count()
+    }
+
+    pub fn ball(&self) -> Option<Pos> {
+        self.ball
+    }
+
+    pub fn paddle(&self) -> Option<Pos> {
+        self.paddle
+    }
+
+    pub fn score(&self) -> isize {
+        self.score
+    }
+}
+
+#[test]
+fn test_arcade() {
+    let mut arcade = Arcade::new(
+        vec![104, 1, 104, 2, 104, 4, 104, -1, 104, 0, 104, 7, 99],
+        false,
+    );
+    let mut events = Vec::new();
+    assert_eq!(
+        arcade.run_with(|event| events.push(*event)),
+        Ok(State::Halt)
+    );
+    assert_eq!(
+        events,
+        vec![
+            Event::DrawTile {
+                pos: (1, 2),
+                tile: Tile::Ball
+            },
+            Event::Score(7)
+        ]
+    );
+    assert_eq!(
+        (arcade.ball(), arcade.score(), arcade.blocks()),
+        (Some((1, 2)), 7, 0)
+    );
+
+    let mut arcade = Arcade::new(vec![104, 1, 104, 2, 104, 9, 99], false);
+    assert_eq!(
+        arcade.run(),
+        Err(ArcadeError::UnknownTile { pos: (1, 2), id: 9 })
+    );
+    let mut arcade = Arcade::new(vec![104, 1, 99], false);
+    assert_eq!(arcade.run(), Err(ArcadeError::Truncated { values: 1 }));
+    let mut arcade = Arcade::new(vec![], true);
+    assert!(matches!(arcade.run(), Err(ArcadeError::Intcode(..))));
+}
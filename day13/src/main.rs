@@ -1,254 +1,290 @@
+mod arcade;
+
+use arcade::{Arcade, ArcadeError, Event, Tile};
+use intcode::render::Screen;
+use intcode::State;
+
 use std::collections::VecDeque;
+use std::io::{self, BufRead};
+use std::time::Duration;
 
-struct IntComp {
-    mem: Vec<isize>,
-    input: VecDeque<isize>,
-    output: VecDeque<isize>,
-    pc: usize,
-    rel_base: isize,
+fn run_part1(mem: Vec<isize>) {
+    let mut arcade = Arcade::new(mem, false);
+    arcade.run().unwrap();
+    println!("Number of block tiles: {}", arcade.blocks());
 }
 
-#[derive(Debug)]
-enum State {
-    Run,
-    NeedsInput,
-    Halt,
+/// The cabinet and a screen showing it.
+struct Game {
+    arcade: Arcade,
+    screen: Screen<Tile>,
+}
+
+impl Game {
+    fn new(mem: Vec<isize>) -> Game {
+        Game {
+            arcade: Arcade::new(mem, true),
+            screen: Screen::new(vec![
+                (Tile::Empty, ' '),
+                (Tile::Wall, '█'),
+                (Tile::Block, '□'),
+                (Tile::Paddle, '⬌'),
+                (Tile::Ball, '●'),
+            ]),
+        }
+    }
+
+    /// Runs until the joystick is read or the game ends, drawing as it goes.
+    fn run(&mut self) -> Result<State, ArcadeError> {
+        let screen = &mut self.screen;
+        self.arcade.run_with(|event| match *event {
+            Event::Score(score) => screen.set_status(format!("SCORE: {}", score)),
+            Event::DrawTile { pos, tile } => screen.set(pos, tile),
+        })
+    }
 }
 
-enum Arg {
-    Imm(isize),
-    Pos(usize),
-    Rel(isize, isize),
+/// Decides which way to push the joystick: -1 for left, 1 for right and 0
+/// to leave the paddle be.
+trait Controller {
+    fn joystick(&mut self, arcade: &Arcade) -> isize;
 }
 
-impl Arg {
-    fn load(&self, mem: &[isize]) -> isize {
-        match self {
-            Arg::Imm(val) => *val,
-            Arg::Pos(val) => mem[*val as usize],
-            Arg::Rel(val, base) => mem[(*val + *base) as usize],
-        }
+fn towards(from: Option<(isize, isize)>, to: Option<(isize, isize)>) -> isize {
+    match (from, to) {
+        (Some(from), Some(to)) => (to.0 - from.0).signum(),
+        _ => 0,
     }
+}
 
-    fn store(&self, val: isize, mem: &mut [isize]) {
-        match self {
-            Arg::Pos(pos) => mem[*pos] = val,
-            Arg::Rel(pos, rel_base) => mem[(*pos + *rel_base) as usize] = val,
-            Arg::Imm(..) => panic!("Can't store to an immediate."),
-        }
+/// Keeps the paddle under the ball.
+struct Tracking;
+
+impl Controller for Tracking {
+    fn joystick(&mut self, arcade: &Arcade) -> isize {
+        towards(arcade.paddle(), arcade.ball())
     }
 }
 
-enum Instr {
-    Add(Arg, Arg, Arg),
-    Mul(Arg, Arg, Arg),
-    Input(Arg),
-    Output(Arg),
-    JumpIf(Arg, Arg),
-    JumpUnless(Arg, Arg),
-    LessThan(Arg, Arg, Arg),
-    Equals(Arg, Arg, Arg),
-    SetRelBase(Arg),
-    Halt,
-}
-
-impl Instr {
-    fn execute(&self, comp: &mut IntComp) -> State {
-        use Instr::*;
-        match self {
-            Add(arg1, arg2, dest) => {
-                dest.store(arg1.load(&comp.mem) + arg2.load(&comp.mem), &mut comp.mem)
-            }
-            Mul(arg1, arg2, dest) => {
-                dest.store(arg1.load(&comp.mem) * arg2.load(&comp.mem), &mut comp.mem)
-            }
-            Input(arg) => arg.store(comp.input.pop_front().unwrap(), &mut comp.mem),
-            Output(arg) => comp.output.push_back(arg.load(&comp.mem)),
-            JumpIf(arg, new_pc) => {
-                if arg.load(&comp.mem) != 0 {
-                    comp.pc = new_pc.load(&comp.mem) as usize;
-                }
-            }
-            JumpUnless(arg, new_pc) => {
-                if arg.load(&comp.mem) == 0 {
-                    comp.pc = new_pc.load(&comp.mem) as usize;
-                }
-            }
-            LessThan(arg1, arg2, dest) => dest.store(
-                if arg1.load(&comp.mem) < arg2.load(&comp.mem) {
-                    1
-                } else {
-                    0
-                },
-                &mut comp.mem,
-            ),
-            Equals(arg1, arg2, dest) => dest.store(
-                if arg1.load(&comp.mem) == arg2.load(&comp.mem) {
-                    1
-                } else {
-                    0
-                },
-                &mut comp.mem,
-            ),
-            SetRelBase(arg) => comp.rel_base += arg.load(&comp.mem),
-            Halt => (),
-        };
+/// Reads a line per move: `a` for left, `d` for right, anything else to
+/// stay put.
+struct Keyboard<R>(R);
 
-        if let Halt = self {
-            State::Halt
-        } else {
-            State::Run
+impl<R: BufRead> Controller for Keyboard<R> {
+    fn joystick(&mut self, _arcade: &Arcade) -> isize {
+        let mut line = String::new();
+        let _ = self.0.read_line(&mut line);
+        match line.trim() {
+            "a" => -1,
+            "d" => 1,
+            _ => 0,
         }
     }
 }
 
-impl IntComp {
-    fn new(mem: Vec<isize>, input: &[isize]) -> IntComp {
-        IntComp {
-            mem,
-            input: input.iter().cloned().collect(),
-            output: VecDeque::new(),
-            pc: 0,
-            rel_base: 0,
+/// Heads for where the ball will next come down to the paddle, found by
+/// playing a copy of the game forward with the paddle still.
+#[derive(Default)]
+struct Predictive {
+    target: Option<(isize, isize)>,
+    landed: bool,
+}
+
+impl Predictive {
+    fn predict(arcade: &Arcade) -> Option<(isize, isize)> {
+        let row = arcade.paddle()?.1 - 1;
+        let mut sim = arcade.clone();
+        let mut last = sim.ball()?;
+        loop {
+            sim.joystick(0);
+            if sim.run().ok()? == State::Halt {
+                return None;
+            }
+            let ball = sim.ball()?;
+            if ball.1 == row && ball.1 > last.1 {
+                return Some(ball);
+            }
+            last = ball;
         }
     }
-    fn decode(&self) -> (Instr, usize) {
-        use Instr::*;
-        let mut pc = self.pc;
-        let instr = self.mem[pc];
-        pc += 1;
-        // this closure will increment pc and shift mode.
-        let mut mode = instr / 100;
-        let pc_ref = &mut pc;
-        let mut make_arg = move || {
-            let val = self.mem[*pc_ref];
-            *pc_ref += 1;
-            let ret = match mode % 10 {
-                0 => Arg::Pos(val as usize),
-                1 => Arg::Imm(val),
-                2 => Arg::Rel(val, self.rel_base),
-                i => panic!("Unknown mode {}", i),
-            };
-            mode /= 10;
-            ret
-        };
+}
 
-        let instr = match instr % 100 {
-            1 => Add(make_arg(), make_arg(), make_arg()),
-            2 => Mul(make_arg(), make_arg(), make_arg()),
-            3 => Input(make_arg()),
-            4 => Output(make_arg()),
-            5 => JumpIf(make_arg(), make_arg()),
-            6 => JumpUnless(make_arg(), make_arg()),
-            7 => LessThan(make_arg(), make_arg(), make_arg()),
-            8 => Equals(make_arg(), make_arg(), make_arg()),
-            9 => SetRelBase(make_arg()),
-            99 => Halt,
-            i => panic!("unknown opcode {} at pc {}", i, self.pc),
+impl Controller for Predictive {
+    fn joystick(&mut self, arcade: &Arcade) -> isize {
+        // hold still until the ball has bounced, then look ahead again.
+        let landing = match (arcade.ball(), arcade.paddle()) {
+            (Some(ball), Some(paddle)) => ball.1 == paddle.1 - 1,
+            _ => false,
         };
-        (instr, pc)
-    }
-    fn step(&mut self) -> State {
-        let (instr, new_pc) = self.decode();
-        if let Instr::Input(..) = instr {
-            if self.input.is_empty() {
-                return State::NeedsInput;
-            }
+        if self.landed && !landing {
+            self.target = None;
         }
-        self.pc = new_pc;
-        instr.execute(self)
+        self.landed = landing;
+        if self.target.is_none() {
+            self.target = Predictive::predict(arcade);
+        }
+        towards(arcade.paddle(), self.target.or_else(|| arcade.ball()))
     }
 }
 
-use std::collections::HashMap;
+/// Plays back recorded moves, then leaves the paddle be.
+struct Replay(VecDeque<isize>);
 
-fn run_part1(mem: Vec<isize>) {
-    let mut comp = IntComp::new(mem, &[]);
-    while let State::Run = comp.step() {}
-
-    let mut map = HashMap::new();
-    while let (Some(x), Some(y), Some(tile_id)) = (
-        comp.output.pop_front(),
-        comp.output.pop_front(),
-        comp.output.pop_front(),
-    ) {
-        if tile_id == 2 {
-            // block type
-            map.insert((x, y), tile_id);
-        }
+impl Controller for Replay {
+    fn joystick(&mut self, _arcade: &Arcade) -> isize {
+        self.0.pop_front().unwrap_or(0)
+    }
+}
+
+/// Plays a game to the end, returning the final score and every joystick
+/// move made. With a `delay`, the game is drawn on the terminal as it goes.
+fn play(
+    mem: Vec<isize>,
+    controller: &mut impl Controller,
+    delay: Option<Duration>,
+) -> Result<(isize, Vec<isize>), ArcadeError> {
+    let mut game = Game::new(mem);
+    if delay.is_some() {
+        game.screen
+            .go_live(Box::new(std::io::stdout()), (0, 0))
+            .unwrap();
     }
-    println!("Number of block tiles: {}", map.len());
-}
-
-fn run_part2(mut mem: Vec<isize>) {    
-    mem[0] = 2; // per instructions
-    let mut comp = IntComp::new(mem, &[]);
-    let mut last_ball = 0;
-    let mut last_paddle = 0;
-    let mut max_y = 0;
-    
-    print!("{}{}", ansi_escapes::ClearScreen, ansi_escapes::CursorHide);
-
-    loop {
-        match comp.step() {
-            State::Halt => break,
-            State::Run => (),
-            State::NeedsInput => {
-                use std::cmp::Ordering::*;
-                comp.input.push_back(match last_paddle.cmp(&last_ball) {
-                    Greater => -1,
-                    Less => 1,
-                    Equal => 0,
-                });
-                // use input as a chance to flush and induce some delay.
-                use std::io::Write;
-                let _ = std::io::stdout().lock().flush();    
-                std::thread::sleep(std::time::Duration::from_millis(1));
-            },
+    let mut moves = Vec::new();
+    let result = loop {
+        match game.run() {
+            Ok(State::NeedsInput) => (),
+            Ok(_) => break Ok(()),
+            Err(e) => break Err(e),
         }
-        while comp.output.len() >= 3 {
-            let (x, y, tile_id) = (
-                comp.output.pop_front().unwrap(),
-                comp.output.pop_front().unwrap(),
-                comp.output.pop_front().unwrap(),
-            );
-            if x == -1 && y == 0 {
-                print!("{}SCORE: {}", ansi_escapes::CursorTo::AbsoluteXY(0,0), tile_id);
-            } else {
-                // it's a draw instruction
-                let out = match tile_id {
-                    0 => ' ', // is an empty tile. No game object appears in this tile.
-                    1 => '█', // is a wall tile. Walls are indestructible barriers.
-                    2 => '□', // is a block tile. Blocks can be broken by the ball.
-                    3 => {
-                        last_paddle = x;
-                        '⬌' // is a horizontal paddle tile. The paddle is indestructible.
-                    },
-                    4 => {
-                        last_ball = x;
-                        '●' // is a ball tile. The ball moves diagonally and bounces off objects.
-                    },
-                    i => panic!("unknown tile type: {}", i),
-                };
-                print!("{}{}", ansi_escapes::CursorTo::AbsoluteXY((y+1) as u16, x as u16), out);
-                max_y = max_y.max(y+1);
-            }
+        if let Some(delay) = delay {
+            // show the frame being answered, then induce some delay.
+            game.screen.flush().unwrap();
+            std::thread::sleep(delay);
         }
+        let joystick = controller.joystick(&game.arcade);
+        moves.push(joystick);
+        game.arcade.joystick(joystick);
+    };
+    // for tidyness, move past the board, even if the game went wrong.
+    game.screen.finish().unwrap();
+    result.map(|()| (game.arcade.score(), moves))
+}
+
+/// A recording is the final score on the first line and the moves, comma
+/// separated, on the second.
+fn save_recording(path: &str, score: isize, moves: &[isize]) -> io::Result<()> {
+    let moves: Vec<_> = moves.iter().map(|m| m.to_string()).collect();
+    std::fs::write(path, format!("{}\n{}\n", score, moves.join(",")))
+}
+
+fn load_recording(path: &str) -> io::Result<(isize, VecDeque<isize>)> {
+    let text = std::fs::read_to_string(path)?;
+    let mut lines = text.lines();
+    let bad = || io::Error::new(io::ErrorKind::InvalidData, "bad recording");
+    let score = lines
+        .next()
+        .and_then(|line| line.parse().ok())
+        .ok_or_else(bad)?;
+    let moves = match lines.next().map(str::trim) {
+        None | Some("") => Vec::new(),
+        Some(line) => intcode::parse_program(line).map_err(|_| bad())?,
+    };
+    Ok((score, moves.into()))
+}
+
+/// Options: `--fast` plays without drawing or delay, `--human` or
+/// `--predict` pick the controller, `--record <path>` saves the moves and
+/// `--replay <path>` plays saved moves back and checks the score. A human
+/// needs to see the board, so `--human` can't be `--fast`.
+fn run_part2(mem: Vec<isize>) {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let flag = |name: &str| args.iter().any(|arg| arg == name);
+    let value = |name: &str| {
+        args.iter()
+            .position(|arg| arg == name)
+            .and_then(|i| args.get(i + 1))
+    };
+    if flag("--human") && flag("--fast") {
+        eprintln!("--human needs the board drawn, so it can't be --fast");
+        std::process::exit(2);
     }
+    let delay = Some(Duration::from_millis(1)).filter(|_| !flag("--fast"));
 
-    // for tidyness, move past the board.
-    print!("{}", ansi_escapes::CursorTo::AbsoluteXY(max_y as u16 + 5, 0));
-    print!("{}", ansi_escapes::CursorShow);
-    
+    let played = if let Some(path) = value("--replay") {
+        let (expected, moves) = load_recording(path).unwrap();
+        let played = play(mem, &mut Replay(moves), delay);
+        if let Ok((score, _)) = played {
+            println!("replay scored {}, recording says {}", score, expected);
+            if score != expected {
+                eprintln!("The replay doesn't match the recording!");
+                std::process::exit(1);
+            }
+        }
+        played
+    } else if flag("--human") {
+        let stdin = io::stdin();
+        play(mem, &mut Keyboard(stdin.lock()), delay)
+    } else if flag("--predict") {
+        play(mem, &mut Predictive::default(), delay)
+    } else {
+        play(mem, &mut Tracking, delay)
+    };
+    let (score, moves) = match played {
+        Ok(played) => played,
+        Err(e) => {
+            eprintln!("The arcade went wrong: {}", e);
+            return;
+        }
+    };
+    println!("Final score: {} after {} moves", score, moves.len());
+    if let Some(path) = value("--record") {
+        save_recording(path, score, &moves).unwrap();
+    }
 }
 
 fn main() {
-    let mut mem: Vec<isize> = include_str!("input.txt")
-        .trim()
-        .split(',')
-        .map(|s| s.parse().unwrap())
-        .collect();
-    mem.resize(mem.len() + 10000, 0);
+    let mem = intcode::parse_program(include_str!("input.txt")).unwrap();
     run_part1(mem.clone());
     run_part2(mem.clone());
 }
+
+#[test]
+fn test_recording() {
+    let path = std::env::temp_dir().join(format!("day13-test-{}.txt", std::process::id()));
+    let path = path.to_str().unwrap();
+    for moves in &[vec![-1, 0, 1, 1], vec![]] {
+        save_recording(path, 42, moves).unwrap();
+        let (score, loaded) = load_recording(path).unwrap();
+        assert_eq!((score, Vec::from(loaded)), (42, moves.clone()));
+    }
+    std::fs::write(path, "lots\n1,0\n").unwrap();
+    assert_eq!(
+        load_recording(path).unwrap_err().kind(),
+        io::ErrorKind::InvalidData
+    );
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_controllers() {
+    let arcade = Arcade::new(vec![99], false);
+    let mut keyboard = Keyboard(io::Cursor::new("a\nd\nx\n\n"));
+    let moves: Vec<_> = (0..5).map(|_| keyboard.joystick(&arcade)).collect();
+    assert_eq!(moves, vec![-1, 1, 0, 0, 0]);
+
+    // a game played by tracking the ball, then played back.
+    let mem = intcode::parse_program(include_str!("input.txt")).unwrap();
+    let (score, moves) = play(mem.clone(), &mut Tracking, None).unwrap();
+    assert_eq!(score, 21651);
+    let replay = play(mem.clone(), &mut Replay(moves.clone().into()), None).unwrap();
+    assert_eq!(replay, (score, moves));
+
+    // looking ahead clears the board just the same.
+    let mut arcade = Arcade::new(mem, true);
+    let mut predictive = Predictive::default();
+    while arcade.run().unwrap() == State::NeedsInput {
+        let joystick = predictive.joystick(&arcade);
+        arcade.joystick(joystick);
+    }
+    assert_eq!((arcade.blocks(), arcade.score()), (0, score));
+}
@@ -0,0 +1,159 @@
+//! Mapping the area by driving the repair droid around. `on_cell` is told
+//! about each cell as it's discovered, for drawing the search as it goes.
+
+use crate::maze::{Cell, Dir, Maze, Pos};
+use intcode::{IntComp, IntcodeError, State};
+use std::collections::VecDeque;
+use std::fmt;
+
+#[derive(Debug, PartialEq)]
+pub enum DroidError {
+    Intcode(IntcodeError),
+    /// The droid answered a move with something other than 0, 1 or 2.
+    UnknownStatus {
+        status: isize,
+    },
+    /// The droid stopped without answering a move.
+    NoStatus {
+        state: State,
+    },
+    /// The droid hit a wall going back the way it came.
+    CantBackUp {
+        pos: Pos,
+        dir: Dir,
+    },
+}
+
+impl fmt::Display for DroidError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DroidError::Intcode(e) => write!(f, "{}", e),
+            DroidError::UnknownStatus { status } => write!(f, "unknown droid status {}", status),
+            DroidError::NoStatus { state } => {
+                write!(f, "droid stopped ({:?}) without a status", state)
+            }
+            DroidError::CantBackUp { pos, dir } => {
+                write!(f, "droid couldn't back up {:?} from {:?}", dir, pos)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DroidError {}
+
+impl From<IntcodeError> for DroidError {
+    fn from(e: IntcodeError) -> DroidError {
+        DroidError::Intcode(e)
+    }
+}
+
+/// Tries to move the droid, reporting what it found there. The droid only
+/// moves if that's not a wall.
+fn try_move(comp: &mut IntComp, dir: Dir) -> Result<Cell, DroidError> {
+    comp.input.push_back(dir as isize);
+    let state = comp.run_until_io()?;
+    match comp.output.pop_front() {
+        Some(0) => Ok(Cell::Wall),
+        Some(1) => Ok(Cell::Open),
+        Some(2) => Ok(Cell::Oxygen),
+        Some(status) => Err(DroidError::UnknownStatus { status }),
+        None => Err(DroidError::NoStatus { state }),
+    }
+}
+
+/// Breadth first, with a copy of the droid for each cell reached.
+pub fn explore_copies(
+    mem: Vec<isize>,
+    mut on_cell: impl FnMut(Pos, Cell),
+) -> Result<Maze, DroidError> {
+    let mut maze = Maze::new();
+    on_cell((0, 0), Cell::Open);
+    let mut queue = VecDeque::new();
+    queue.push_back((IntComp::new(mem, &[]), (0, 0)));
+    while let Some((droid, pos)) = queue.pop_front() {
+        for &dir in Dir::ALL.iter() {
+            let next = dir.step(pos);
+            if maze.get(next).is_some() {
+                continue;
+            }
+            let mut droid = droid.clone();
+            let cell = try_move(&mut droid, dir)?;
+            maze.set(next, cell);
+            on_cell(next, cell);
+            if cell != Cell::Wall {
+                queue.push_back((droid, next));
+            }
+        }
+    }
+    Ok(maze)
+}
+
+/// Depth first with the one droid, backing it up when it runs out of new
+/// places to go. The droid ends up back where it started.
+pub fn explore_droid(
+    mem: Vec<isize>,
+    mut on_cell: impl FnMut(Pos, Cell),
+) -> Result<Maze, DroidError> {
+    let mut maze = Maze::new();
+    on_cell((0, 0), Cell::Open);
+    let mut droid = IntComp::new(mem, &[]);
+    let mut pos = (0, 0);
+    // the moves that got the droid to `pos`.
+    let mut path: Vec<Dir> = Vec::new();
+    loop {
+        let unknown = Dir::ALL
+            .iter()
+            .copied()
+            .find(|dir| maze.get(dir.step(pos)).is_none());
+        match unknown {
+            Some(dir) => {
+                let next = dir.step(pos);
+                let cell = try_move(&mut droid, dir)?;
+                maze.set(next, cell);
+                on_cell(next, cell);
+                if cell != Cell::Wall {
+                    pos = next;
+                    path.push(dir);
+                }
+            }
+            None => match path.pop() {
+                Some(dir) => {
+                    let back = dir.back();
+                    if try_move(&mut droid, back)? == Cell::Wall {
+                        return Err(DroidError::CantBackUp { pos, dir: back });
+                    }
+                    pos = back.step(pos);
+                }
+                None => return Ok(maze),
+            },
+        }
+    }
+}
+
+#[test]
+fn test_droid_errors() {
+    // answers the first move with status 7.
+    let bad_status = vec![3, 100, 104, 7, 99];
+    assert_eq!(
+        explore_copies(bad_status.clone(), |_, _| ()),
+        Err(DroidError::UnknownStatus { status: 7 })
+    );
+    assert_eq!(
+        explore_droid(bad_status, |_, _| ()),
+        Err(DroidError::UnknownStatus { status: 7 })
+    );
+    // halts without answering.
+    assert_eq!(
+        explore_droid(vec![3, 100, 99], |_, _| ()),
+        Err(DroidError::NoStatus { state: State::Halt })
+    );
+    // moves once, then finds walls everywhere, including the way back.
+    let walled_in = vec![3, 100, 104, 1, 3, 100, 104, 0, 1105, 1, 4];
+    assert_eq!(
+        explore_droid(walled_in, |_, _| ()),
+        Err(DroidError::CantBackUp {
+            pos: Dir::ALL[0].step((0, 0)),
+            dir: Dir::ALL[0].back(),
+        })
+    );
+}
@@ -0,0 +1,253 @@
+//! What the repair droid has found of the area, and a plain text form of it
+//! that can be saved and loaded again.
+//!
+//! In the text form `#` is a wall, `.` open floor and `O` the oxygen system.
+//! The cell the droid started on, which is (0, 0), is marked `D` if it's
+//! open floor, `@` if it's the oxygen system and `X` if it's been walled
+//! over. Spaces, and anything past the end of a line, haven't been explored.
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::str::FromStr;
+
+pub type Pos = (isize, isize);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Cell {
+    Wall,
+    Open,
+    Oxygen,
+}
+
+/// The droid's movement commands, in command order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Dir {
+    North = 1,
+    South = 2,
+    West = 3,
+    East = 4,
+}
+
+impl Dir {
+    pub const ALL: [Dir; 4] = [Dir::North, Dir::South, Dir::West, Dir::East];
+
+    pub fn step(self, pos: Pos) -> Pos {
+        match self {
+            Dir::North => (pos.0, pos.1 - 1),
+            Dir::South => (pos.0, pos.1 + 1),
+            Dir::West => (pos.0 - 1, pos.1),
+            Dir::East => (pos.0 + 1, pos.1),
+        }
+    }
+
+    pub fn back(self) -> Dir {
+        match self {
+            Dir::North => Dir::South,
+            Dir::South => Dir::North,
+            Dir::West => Dir::East,
+            Dir::East => Dir::West,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Maze {
+    cells: HashMap<Pos, Cell>,
+}
+
+impl Maze {
+    /// A maze where only the start is known, and open.
+    pub fn new() -> Maze {
+        let mut cells = HashMap::new();
+        cells.insert((0, 0), Cell::Open);
+        Maze { cells }
+    }
+
+    pub fn get(&self, pos: Pos) -> Option<Cell> {
+        self.cells.get(&pos).copied()
+    }
+
+    pub fn set(&mut self, pos: Pos, cell: Cell) {
+        self.cells.insert(pos, cell);
+    }
+
+    pub fn cells(&self) -> &HashMap<Pos, Cell> {
+        &self.cells
+    }
+
+    /// Where the oxygen system is, if it's been found. Maps can have more
+    /// than one, like flood snapshots, so this is the one fewest moves from
+    /// the start, ties and unreachable ones going to the lowest position.
+    pub fn oxygen(&self) -> Option<Pos> {
+        let dists = self.distances((0, 0));
+        self.cells
+            .iter()
+            .filter(|(_, &cell)| cell == Cell::Oxygen)
+            .map(|(&pos, _)| pos)
+            .min_by_key(|pos| (dists.get(pos).copied().unwrap_or(usize::MAX), *pos))
+    }
+
+    /// How many moves it takes to reach each known cell that isn't a wall
+    /// from `from`.
+    pub fn distances(&self, from: Pos) -> HashMap<Pos, usize> {
+        let mut dists = HashMap::new();
+        dists.insert(from, 0);
+        let mut queue = VecDeque::new();
+        queue.push_back(from);
+        while let Some(pos) = queue.pop_front() {
+            let dist = dists[&pos] + 1;
+            for dir in Dir::ALL.iter() {
+                let next = dir.step(pos);
+                match self.get(next) {
+                    Some(Cell::Wall) | None => continue,
+                    Some(_) if dists.contains_key(&next) => continue,
+                    Some(_) => {
+                        dists.insert(next, dist);
+                        queue.push_back(next);
+                    }
+                }
+            }
+        }
+        dists
+    }
+
+    fn bounds(&self) -> (Pos, Pos) {
+        self.cells
+            .keys()
+            .fold(((0, 0), (0, 0)), |(min, max), &(x, y)| {
+                ((min.0.min(x), min.1.min(y)), (max.0.max(x), max.1.max(y)))
+            })
+    }
+}
+
+impl fmt::Display for Maze {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (min, max) = self.bounds();
+        for y in min.1..=max.1 {
+            let line: String = (min.0..=max.0)
+                .map(|x| match self.get((x, y)) {
+                    Some(Cell::Open) if (x, y) == (0, 0) => 'D',
+                    Some(Cell::Oxygen) if (x, y) == (0, 0) => '@',
+                    Some(Cell::Wall) if (x, y) == (0, 0) => 'X',
+                    Some(Cell::Wall) => '#',
+                    Some(Cell::Open) => '.',
+                    Some(Cell::Oxygen) => 'O',
+                    None => ' ',
+                })
+                .collect();
+            writeln!(f, "{}", line.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParseMazeError {
+    /// Line and column count from 1.
+    BadChar {
+        line: usize,
+        col: usize,
+        c: char,
+    },
+    NoStart,
+    TwoStarts,
+}
+
+impl fmt::Display for ParseMazeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseMazeError::BadChar { line, col, c } => {
+                write!(f, "unexpected {:?} at line {} column {}", c, line, col)
+            }
+            ParseMazeError::NoStart => write!(f, "no start (D, @ or X) in the map"),
+            ParseMazeError::TwoStarts => write!(f, "more than one start in the map"),
+        }
+    }
+}
+
+impl std::error::Error for ParseMazeError {}
+
+impl FromStr for Maze {
+    type Err = ParseMazeError;
+
+    fn from_str(s: &str) -> Result<Maze, ParseMazeError> {
+        let mut cells = HashMap::new();
+        let mut start = None;
+        for (y, line) in s.lines().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                let pos = (x as isize, y as isize);
+                let cell = match c {
+                    ' ' => continue,
+                    '#' => Cell::Wall,
+                    '.' => Cell::Open,
+                    'O' => Cell::Oxygen,
+                    'D' | '@' | 'X' if start.is_some() => return Err(ParseMazeError::TwoStarts),
+                    'D' | '@' | 'X' => {
+                        start = Some(pos);
+                        match c {
+                            'D' => Cell::Open,
+                            '@' => Cell::Oxygen,
+                            _ => Cell::Wall,
+                        }
+                    }
+                    _ => {
+                        let (line, col) = (y + 1, x + 1);
+                        return Err(ParseMazeError::BadChar { line, col, c });
+                    }
+                };
+                cells.insert(pos, cell);
+            }
+        }
+        let start = start.ok_or(ParseMazeError::NoStart)?;
+        Ok(Maze {
+            cells: cells
+                .into_iter()
+                .map(|((x, y), cell)| ((x - start.0, y - start.1), cell))
+                .collect(),
+        })
+    }
+}
+
+#[test]
+fn test_maze() {
+    let text = " ##\n#..#\n#D.O#\n ###\n";
+    let maze: Maze = text.parse().unwrap();
+    assert_eq!(maze.get((0, 0)), Some(Cell::Open));
+    assert_eq!(maze.get((-1, -1)), Some(Cell::Wall));
+    assert_eq!(maze.get((-1, -2)), None);
+    assert_eq!(maze.oxygen(), Some((2, 0)));
+    assert_eq!(maze.distances((0, 0))[&(2, 0)], 2);
+    assert_eq!(maze.distances((0, 0)).len(), 5);
+    assert_eq!(maze.to_string(), text);
+
+    assert_eq!(
+        "#D\n.x".parse::<Maze>(),
+        Err(ParseMazeError::BadChar {
+            line: 2,
+            col: 2,
+            c: 'x'
+        })
+    );
+    assert_eq!("#.".parse::<Maze>(), Err(ParseMazeError::NoStart));
+    assert_eq!("D.@".parse::<Maze>(), Err(ParseMazeError::TwoStarts));
+
+    // with several oxygen systems, the nearest wins.
+    for &(text, oxygen) in &[
+        ("O...D.O.O\n", (2, 0)),
+        ("O.D.O.O\n", (-2, 0)),
+        ("O#D..O\n", (3, 0)),
+        ("O#D#O\n", (-2, 0)),
+    ] {
+        let maze: Maze = text.parse().unwrap();
+        assert_eq!(maze.oxygen(), Some(oxygen), "{:?}", text);
+    }
+
+    // the start keeps whatever is there.
+    for &(cell, text) in &[(Cell::Oxygen, "#@.#\n"), (Cell::Wall, "#X.#\n")] {
+        let mut maze: Maze = "#D.#\n".parse().unwrap();
+        maze.set((0, 0), cell);
+        assert_eq!(maze.to_string(), text);
+        let loaded: Maze = text.parse().unwrap();
+        assert_eq!(loaded, maze);
+    }
+}
@@ -0,0 +1,151 @@
+//! Oxygen spreading through the area a minute at a time: each minute, every
+//! open cell next to an oxygenated one fills up too.
+
+use crate::maze::{Cell, Dir, Maze, Pos};
+use intcode::render::Screen;
+use std::collections::HashSet;
+
+pub struct Flood {
+    maze: Maze,
+    oxygenated: HashSet<Pos>,
+    /// Cells filled in the last minute, the only ones that can spread.
+    front: Vec<Pos>,
+    minute: usize,
+}
+
+impl Flood {
+    /// Starts with oxygen at each oxygen system in `maze`.
+    pub fn new(maze: Maze) -> Flood {
+        let mut flood = Flood {
+            maze,
+            oxygenated: HashSet::new(),
+            front: Vec::new(),
+            minute: 0,
+        };
+        let mut sources: Vec<_> = flood
+            .maze
+            .cells()
+            .iter()
+            .filter(|(_, &cell)| cell == Cell::Oxygen)
+            .map(|(&pos, _)| pos)
+            .collect();
+        sources.sort_unstable();
+        for pos in sources {
+            flood.add_source(pos);
+        }
+        flood
+    }
+
+    /// Lets oxygen out at `pos` from now on.
+    pub fn add_source(&mut self, pos: Pos) {
+        self.maze.set(pos, Cell::Oxygen);
+        if self.oxygenated.insert(pos) {
+            self.front.push(pos);
+        }
+    }
+
+    /// Walls `pos` off, even if it already has oxygen.
+    pub fn add_wall(&mut self, pos: Pos) {
+        self.maze.set(pos, Cell::Wall);
+        self.oxygenated.remove(&pos);
+        self.front.retain(|&front| front != pos);
+    }
+
+    /// Every cell with oxygen in it so far, sources included.
+    pub fn oxygenated(&self) -> &HashSet<Pos> {
+        &self.oxygenated
+    }
+
+    pub fn minute(&self) -> usize {
+        self.minute
+    }
+
+    /// Lets a minute pass, returning the cells that filled. Once nothing
+    /// fills, the clock stops.
+    pub fn step(&mut self) -> Vec<Pos> {
+        let mut filled = Vec::new();
+        for &pos in &self.front {
+            for dir in Dir::ALL.iter() {
+                let next = dir.step(pos);
+                match self.maze.get(next) {
+                    Some(Cell::Wall) | None => (),
+                    Some(_) => {
+                        if self.oxygenated.insert(next) {
+                            filled.push(next);
+                        }
+                    }
+                }
+            }
+        }
+        if !filled.is_empty() {
+            self.minute += 1;
+        }
+        self.front = filled.clone();
+        filled
+    }
+
+    /// Runs until the oxygen can't get any further, returning how many
+    /// minutes that took in all.
+    pub fn run(&mut self) -> usize {
+        while !self.step().is_empty() {}
+        self.minute
+    }
+
+    /// Shows the oxygenated cells as oxygen systems.
+    pub fn draw(&self, screen: &mut Screen<Cell>) {
+        for (&pos, &cell) in self.maze.cells() {
+            let cell = if self.oxygenated.contains(&pos) {
+                Cell::Oxygen
+            } else {
+                cell
+            };
+            screen.set(pos, cell);
+        }
+    }
+
+    /// The area in the maze text form, oxygenated cells marked as oxygen
+    /// systems, so a snapshot can be loaded as a map itself.
+    pub fn snapshot(&self) -> String {
+        let mut maze = self.maze.clone();
+        for &pos in &self.oxygenated {
+            maze.set(pos, Cell::Oxygen);
+        }
+        maze.to_string()
+    }
+}
+
+#[test]
+fn test_flood() {
+    let maze: Maze = "#######\n#D....#\n#.##O.#\n#######\n".parse().unwrap();
+    let mut flood = Flood::new(maze.clone());
+    assert_eq!(flood.step().len(), 2);
+    assert_eq!(flood.snapshot(), "#######\n#D..O.#\n#.##OO#\n#######\n");
+    assert_eq!(flood.run(), 5);
+    assert!(!flood.snapshot().contains('.'));
+
+    let mut flood = Flood::new(maze.clone());
+    flood.add_source((0, 1));
+    assert_eq!(flood.run(), 2);
+
+    let mut flood = Flood::new(maze.clone());
+    flood.add_wall((2, 0));
+    assert_eq!(flood.run(), 2);
+    assert_eq!(flood.snapshot(), "#######\n#D.#OO#\n#.##OO#\n#######\n");
+
+    let mut flood = Flood::new(maze);
+    flood.add_source((0, 1));
+    flood.add_wall((2, 0));
+    let cells = |cells: &[Pos]| cells.iter().copied().collect::<HashSet<_>>();
+    assert_eq!(flood.oxygenated(), &cells(&[(3, 1), (0, 1)]));
+    flood.step();
+    assert_eq!(flood.minute(), 1);
+    assert_eq!(
+        flood.oxygenated(),
+        &cells(&[(3, 1), (0, 1), (3, 0), (4, 1), (0, 0)])
+    );
+    assert_eq!(flood.run(), 2);
+    assert_eq!(
+        flood.oxygenated(),
+        &cells(&[(3, 1), (0, 1), (3, 0), (4, 1), (0, 0), (4, 0), (1, 0)])
+    );
+}
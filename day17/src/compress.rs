@@ -0,0 +1,176 @@
+//! Splitting a movement path into a main routine that calls a few movement
+//! functions, as the vacuum robot takes its instructions.
+
+use std::fmt;
+
+/// Line lengths don't count the newline.
+pub struct Limits {
+    /// How many movement functions there are.
+    pub routines: usize,
+    /// The longest the main routine may be.
+    pub main_bytes: usize,
+    /// The longest each movement function may be.
+    pub routine_bytes: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            routines: 3,
+            main_bytes: 20,
+            routine_bytes: 20,
+        }
+    }
+}
+
+/// `main` lists the functions called, by index. Functions nothing calls are
+/// left empty.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Encoding {
+    pub main: Vec<usize>,
+    pub routines: Vec<Vec<String>>,
+}
+
+impl Encoding {
+    /// The path the encoding describes.
+    pub fn expand(&self) -> Vec<&str> {
+        self.main
+            .iter()
+            .flat_map(|&i| self.routines[i].iter().map(|s| s.as_str()))
+            .collect()
+    }
+
+    fn bytes(&self) -> usize {
+        self.to_string().len()
+    }
+}
+
+/// The lines the robot reads, each ending in a newline.
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let main: Vec<_> = self
+            .main
+            .iter()
+            .map(|&i| ((b'A' + i as u8) as char).to_string())
+            .collect();
+        writeln!(f, "{}", main.join(","))?;
+        for routine in &self.routines {
+            writeln!(f, "{}", routine.join(","))?;
+        }
+        Ok(())
+    }
+}
+
+fn line_len(tokens: &[&str]) -> usize {
+    tokens.iter().map(|token| token.len()).sum::<usize>() + tokens.len().saturating_sub(1)
+}
+
+/// Every way of encoding `path`, a comma separated list of moves like
+/// `R,8,L,10`, within `limits`. Encodings making fewer calls come first,
+/// then shorter ones.
+pub fn compress(path: &str, limits: &Limits) -> Vec<Encoding> {
+    let tokens: Vec<_> = path.split(',').filter(|token| !token.is_empty()).collect();
+    let mut found = Vec::new();
+    search(
+        &tokens,
+        0,
+        &mut Vec::new(),
+        &mut Vec::new(),
+        limits,
+        &mut found,
+    );
+    found.sort_by_key(|encoding| (encoding.main.len(), encoding.bytes()));
+    found
+}
+
+/// Encodes `tokens[pos..]`, trying each function already defined that
+/// matches here, then each new one that fits.
+fn search<'a>(
+    tokens: &'a [&'a str],
+    pos: usize,
+    routines: &mut Vec<&'a [&'a str]>,
+    main: &mut Vec<usize>,
+    limits: &Limits,
+    found: &mut Vec<Encoding>,
+) {
+    if pos == tokens.len() {
+        let mut encoded: Vec<_> = routines
+            .iter()
+            .map(|routine| routine.iter().map(|s| s.to_string()).collect())
+            .collect();
+        encoded.resize(limits.routines, Vec::new());
+        found.push(Encoding {
+            main: main.clone(),
+            routines: encoded,
+        });
+        return;
+    }
+    // each call takes a letter and a comma, bar the last comma.
+    if 2 * main.len() + 1 > limits.main_bytes {
+        return;
+    }
+    for i in 0..routines.len() {
+        let routine = routines[i];
+        if tokens[pos..].starts_with(routine) {
+            main.push(i);
+            search(tokens, pos + routine.len(), routines, main, limits, found);
+            main.pop();
+        }
+    }
+    if routines.len() < limits.routines {
+        for end in pos + 1..=tokens.len() {
+            let routine = &tokens[pos..end];
+            if line_len(routine) > limits.routine_bytes {
+                break;
+            }
+            if routines.contains(&routine) {
+                continue;
+            }
+            routines.push(routine);
+            main.push(routines.len() - 1);
+            search(tokens, end, routines, main, limits, found);
+            main.pop();
+            routines.pop();
+        }
+    }
+}
+
+#[test]
+fn test_limits() {
+    let path = "R,8,R,8,R,4,R,4,R,8,L,6,L,2,R,4,R,4,R,8,R,8,R,8,L,6,L,2";
+    let fits = |encoding: &Encoding, limits: &Limits| {
+        let text = encoding.to_string();
+        let mut lines = text.lines();
+        lines.next().unwrap().len() <= limits.main_bytes
+            && lines.all(|line| line.len() <= limits.routine_bytes)
+    };
+
+    // only room to call each function once.
+    let short_main = Limits {
+        routines: 3,
+        main_bytes: 5,
+        routine_bytes: 20,
+    };
+    let found = compress(path, &short_main);
+    assert!(!found.is_empty());
+    assert!(found.iter().all(|encoding| fits(encoding, &short_main)));
+
+    // too short for R,4,R,4,R,8, which forces more calls.
+    let short_routines = Limits {
+        routines: 3,
+        main_bytes: 20,
+        routine_bytes: 7,
+    };
+    let found = compress(path, &short_routines);
+    assert_eq!(
+        found[0].to_string(),
+        "A,A,B,A,C,B,A,A,A,C\nR,8\nR,4,R,4\nL,6,L,2\n"
+    );
+    assert!(found.iter().all(|encoding| fits(encoding, &short_routines)));
+
+    let both = Limits {
+        main_bytes: 5,
+        ..short_routines
+    };
+    assert!(compress(path, &both).is_empty());
+}
@@ -0,0 +1,9 @@
+use std::path::Path;
+
+fn main() {
+    let src = std::fs::read_to_string("src/input.txt").unwrap();
+    let image = intcode::parse_program(&src).unwrap();
+    let out = Path::new(&std::env::var("OUT_DIR").unwrap()).join("drone.rs");
+    std::fs::write(out, intcode::aot::compile(&image, "drone")).unwrap();
+    println!("cargo:rerun-if-changed=src/input.txt");
+}
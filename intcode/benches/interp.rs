@@ -0,0 +1,63 @@
+//! Compares looping over `step` with `run_until_io` on a couple of real
+//! workloads. Run with `cargo bench -p intcode`.
+
+use intcode::{IntComp, State};
+use std::time::{Duration, Instant};
+
+fn load(src: &str) -> Vec<isize> {
+    intcode::parse_program(src).unwrap()
+}
+
+fn by_step(comp: &mut IntComp) {
+    while let State::Run = comp.step().unwrap() {}
+}
+
+fn fast(comp: &mut IntComp) {
+    while let State::Run = comp.run_until_io().unwrap() {}
+}
+
+/// day9 part 2: one long run with no I/O to speak of.
+fn day9(run: fn(&mut IntComp)) -> isize {
+    let mut comp = IntComp::new(load(include_str!("../../day9/src/input.txt")), &[2]);
+    run(&mut comp);
+    comp.output[0]
+}
+
+/// day19 part 1: thousands of short runs on clones of one machine.
+fn day19(run: fn(&mut IntComp)) -> isize {
+    let comp = IntComp::new(load(include_str!("../../day19/src/input.txt")), &[]);
+    let mut count = 0;
+    for y in 0..50 {
+        for x in 0..50 {
+            let mut comp = comp.clone();
+            comp.input.extend(&[x, y]);
+            run(&mut comp);
+            count += comp.output[0];
+        }
+    }
+    count
+}
+
+fn time(name: &str, bench: impl Fn() -> isize) -> Duration {
+    let expected = bench();
+    let iters = 10;
+    let start = Instant::now();
+    for _ in 0..iters {
+        assert_eq!(bench(), expected);
+    }
+    let per_iter = start.elapsed() / iters;
+    println!("{:<20} {:>12?}", name, per_iter);
+    per_iter
+}
+
+fn main() {
+    for (name, bench) in &[("day9", day9 as fn(_) -> _), ("day19", day19)] {
+        let slow = time(&format!("{} step", name), || bench(by_step));
+        let quick = time(&format!("{} run_until_io", name), || bench(fast));
+        println!(
+            "{:<20} {:>11.1}x",
+            "",
+            slow.as_secs_f64() / quick.as_secs_f64()
+        );
+    }
+}
@@ -0,0 +1,174 @@
+//! Day 7's amplifier chains: every stage runs the same program, reading
+//! its phase setting and then signals from the stage before it.
+
+use crate::network::{Control, Framing, Hook, Network, NetworkError, Schedule};
+use crate::{IntComp, IntcodeError};
+use std::collections::VecDeque;
+use std::thread;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Topology {
+    /// Each stage runs once, feeding the next. The first gets a 0 signal.
+    Linear,
+    /// The last stage also feeds the first, until they all halt.
+    Feedback,
+}
+
+/// The best phase ordering found by `optimize`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Best {
+    pub phases: Vec<isize>,
+    pub thrust: isize,
+}
+
+/// Every ordering of `stages` distinct values from `set`.
+pub fn permutations(set: &[isize], stages: usize) -> Vec<Vec<isize>> {
+    if stages == 0 {
+        return vec![vec![]];
+    }
+    let mut perms = Vec::new();
+    for (i, &first) in set.iter().enumerate() {
+        let mut rest = set.to_vec();
+        rest.remove(i);
+        for mut perm in permutations(&rest, stages - 1) {
+            perm.insert(0, first);
+            perms.push(perm);
+        }
+    }
+    perms
+}
+
+struct LastFrom(usize, Option<isize>);
+
+impl Hook for LastFrom {
+    fn deliver(&mut self, from: usize, _to: usize, data: &[isize]) -> Control {
+        if from == self.0 {
+            self.1 = Some(data[0]);
+        }
+        Control::Continue
+    }
+}
+
+/// The last signal the final stage sends, or `None` if it never sends one.
+pub fn thrust(
+    mem: &[isize],
+    phases: &[isize],
+    topology: Topology,
+) -> Result<Option<isize>, IntcodeError> {
+    match topology {
+        Topology::Linear => {
+            let mut signal = 0;
+            for &phase in phases {
+                let mut output = Vec::new();
+                IntComp::new(mem.to_vec(), &[phase, signal])
+                    .run_with(VecDeque::new(), &mut output)?;
+                signal = match output.last() {
+                    Some(&val) => val,
+                    None => return Ok(None),
+                };
+            }
+            Ok(Some(signal))
+        }
+        Topology::Feedback => {
+            if phases.is_empty() {
+                return Ok(None);
+            }
+            let mut net = Network::new(Schedule::UntilBlocked);
+            for (i, &phase) in phases.iter().enumerate() {
+                let next = (i + 1) % phases.len();
+                net.add(IntComp::new(mem.to_vec(), &[phase]), Framing::Stream(next));
+            }
+            net.send(0, &[0]);
+            let mut last = LastFrom(phases.len() - 1, None);
+            net.run(&mut last).map_err(|e| match e {
+                NetworkError::Intcode(e) => e,
+                // every stage streams to the next one round the ring, which
+                // always exists, and streams have no packets to be empty.
+                e => unreachable!("amplifier ring is miswired: {}", e),
+            })?;
+            Ok(last.1)
+        }
+    }
+}
+
+fn best_of(
+    mem: &[isize],
+    perms: &[Vec<isize>],
+    topology: Topology,
+) -> Result<Option<Best>, IntcodeError> {
+    let mut best: Option<Best> = None;
+    for phases in perms {
+        if let Some(thrust) = thrust(mem, phases, topology)? {
+            if best.as_ref().is_none_or(|best| thrust > best.thrust) {
+                best = Some(Best {
+                    phases: phases.clone(),
+                    thrust,
+                });
+            }
+        }
+    }
+    Ok(best)
+}
+
+/// Tries every ordering of `stages` phases from `set`, spread over
+/// `threads` threads, and returns the one giving the most thrust. Ties go
+/// to the ordering `permutations` lists first, however many threads run.
+pub fn optimize(
+    mem: &[isize],
+    stages: usize,
+    set: &[isize],
+    topology: Topology,
+    threads: usize,
+) -> Result<Option<Best>, IntcodeError> {
+    let perms = permutations(set, stages);
+    if threads <= 1 || perms.len() < 2 {
+        return best_of(mem, &perms, topology);
+    }
+    let chunk = perms.len().div_ceil(threads);
+    let results: Vec<_> = thread::scope(|scope| {
+        let handles: Vec<_> = perms
+            .chunks(chunk)
+            .map(|perms| scope.spawn(move || best_of(mem, perms, topology)))
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect()
+    });
+    let mut best: Option<Best> = None;
+    for result in results {
+        if let Some(found) = result? {
+            if best.as_ref().is_none_or(|best| found.thrust > best.thrust) {
+                best = Some(found);
+            }
+        }
+    }
+    Ok(best)
+}
+
+#[test]
+fn test_optimize() {
+    let linear = crate::parse_program("3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0").unwrap();
+    let best = optimize(&linear, 5, &[0, 1, 2, 3, 4], Topology::Linear, 1).unwrap();
+    assert_eq!(
+        best,
+        Some(Best {
+            phases: vec![4, 3, 2, 1, 0],
+            thrust: 43210
+        })
+    );
+    assert_eq!(permutations(&[1, 2, 3], 2).len(), 6);
+
+    let feedback = crate::parse_program(
+        "3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,27,4,27,1001,28,-1,28,1005,28,6,99,0,0,5",
+    )
+    .unwrap();
+    let best = optimize(&feedback, 5, &[5, 6, 7, 8, 9], Topology::Feedback, 4).unwrap();
+    assert_eq!(
+        best,
+        Some(Best {
+            phases: vec![9, 8, 7, 6, 5],
+            thrust: 139629729
+        })
+    );
+}
@@ -0,0 +1,719 @@
+//! Translates an Intcode image into Rust ahead of time.
+//!
+//! `compile` emits a module holding the image and a `run` function which is
+//! one big `match pc`, with an arm per basic block and every operand mode
+//! resolved at compile time. Build scripts can call it on a puzzle input and
+//! `include!` the result:
+//!
+//! ```text
+//! // build.rs
+//! let image = intcode::parse_program(&fs::read_to_string("src/input.txt")?)?;
+//! fs::write(out_dir.join("droid.rs"), intcode::aot::compile(&image, "droid"))?;
+//!
+//! // main.rs
+//! include!(concat!(env!("OUT_DIR"), "/droid.rs"));
+//! let mut droid = droid::new(&[1, 2]);
+//! while let State::Run = droid.run_until_io()? {}
+//! ```
+//!
+//! Code is found by following control flow from address 0. Returns are
+//! indirect jumps, so an address just past an unconditional jump counts as
+//! code too if some instruction mentions it as an immediate. Operands the
+//! program patches through a fixed address are read from memory. Any other
+//! write over code marks it dirty, and dirty blocks, like addresses the
+//! compiler never found, run in the interpreter.
+
+use crate::{decode, Arg, IntComp, IntcodeError, LimitExceeded, Memory, State};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::fmt::Write as _;
+use std::ops::Deref;
+
+/// How compiled code hands control back.
+pub enum Exit {
+    Stop(State),
+    /// Run the instruction at `comp.pc` in the interpreter.
+    Interpret,
+    /// A store landed on a word compiled code depends on.
+    Wrote(usize),
+}
+
+/// What a generated module exports. `run` is passed the set of words
+/// written over since compiling.
+pub struct Program {
+    pub image: &'static [isize],
+    /// Whether compiled code depends on the word at an address.
+    pub is_fixed: fn(usize) -> bool,
+    pub run: fn(&mut IntComp, &BTreeSet<usize>) -> Result<Exit, IntcodeError>,
+}
+
+/// A machine running compiled code. It derefs to the `IntComp` holding its
+/// state; memory writes go through `poke` so that patched code is noticed.
+#[derive(Clone)]
+pub struct Compiled {
+    comp: IntComp,
+    program: &'static Program,
+    dirty: BTreeSet<usize>,
+    interpreted: u64,
+}
+
+impl Compiled {
+    /// Wraps `comp`, whose code may differ from what `program` was compiled
+    /// from.
+    pub fn new(program: &'static Program, comp: IntComp) -> Compiled {
+        let dirty = program
+            .image
+            .iter()
+            .enumerate()
+            .filter(|(addr, word)| (program.is_fixed)(*addr) && comp.mem.get(*addr) != **word)
+            .map(|(addr, _)| addr)
+            .collect();
+        Compiled {
+            comp,
+            program,
+            dirty,
+            interpreted: 0,
+        }
+    }
+
+    pub fn input_mut(&mut self) -> &mut VecDeque<isize> {
+        &mut self.comp.input
+    }
+
+    pub fn output_mut(&mut self) -> &mut VecDeque<isize> {
+        &mut self.comp.output
+    }
+
+    pub fn poke(&mut self, addr: usize, val: isize) -> Result<(), LimitExceeded> {
+        self.comp.mem.set(addr, val)?;
+        self.wrote(addr);
+        Ok(())
+    }
+
+    /// How many instructions have run in the interpreter rather than as
+    /// compiled code.
+    pub fn interpreted(&self) -> u64 {
+        self.interpreted
+    }
+
+    pub fn into_inner(self) -> IntComp {
+        self.comp
+    }
+
+    fn wrote(&mut self, addr: usize) {
+        if (self.program.is_fixed)(addr) {
+            self.dirty.insert(addr);
+        }
+    }
+
+    fn interpret(&mut self) -> Result<State, IntcodeError> {
+        let written = match self.comp.decode() {
+            Ok((instr, _)) => dest(&instr).and_then(|dest| dest.addr().ok()),
+            Err(_) => None,
+        };
+        let state = self.comp.step()?;
+        if let (State::Run, Some(addr)) = (&state, written) {
+            self.wrote(addr);
+        }
+        self.interpreted += 1;
+        Ok(state)
+    }
+
+    /// Behaves exactly like `IntComp::run_until_io`.
+    pub fn run_until_io(&mut self) -> Result<State, IntcodeError> {
+        loop {
+            match (self.program.run)(&mut self.comp, &self.dirty)? {
+                Exit::Stop(state) => return Ok(state),
+                Exit::Wrote(addr) => self.wrote(addr),
+                Exit::Interpret => {
+                    let outputs = self.comp.output.len();
+                    match self.interpret()? {
+                        State::Run if self.comp.output.len() == outputs => (),
+                        state => return Ok(state),
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Deref for Compiled {
+    type Target = IntComp;
+
+    fn deref(&self) -> &IntComp {
+        &self.comp
+    }
+}
+
+/// Runtime support for generated code. On a fault these leave `comp.pc` on
+/// the faulting instruction, as the interpreter does.
+#[doc(hidden)]
+pub mod rt {
+    use crate::{Fault, IntComp, IntcodeError};
+    use std::collections::BTreeSet;
+
+    fn fault(comp: &mut IntComp, pc: usize, fault: Fault) -> IntcodeError {
+        comp.pc = pc;
+        fault.at(pc, comp.mem.get(pc))
+    }
+
+    /// Checks an address computed at run time, or a jump target.
+    #[inline]
+    pub fn addr(comp: &mut IntComp, pc: usize, addr: isize) -> Result<usize, IntcodeError> {
+        if addr < 0 {
+            Err(fault(comp, pc, Fault::NegativeAddress(addr)))
+        } else {
+            Ok(addr as usize)
+        }
+    }
+
+    #[inline]
+    pub fn rel(comp: &mut IntComp, pc: usize, offset: isize) -> Result<usize, IntcodeError> {
+        let sum = comp.rel_base.checked_add(offset);
+        let sum = checked(comp, pc, sum)?;
+        addr(comp, pc, sum)
+    }
+
+    /// The result of checked arithmetic, faulting if it overflowed.
+    #[inline]
+    pub fn checked(
+        comp: &mut IntComp,
+        pc: usize,
+        val: Option<isize>,
+    ) -> Result<isize, IntcodeError> {
+        val.ok_or_else(|| fault(comp, pc, Fault::Overflow))
+    }
+
+    #[inline]
+    pub fn is_dirty(dirty: &BTreeSet<usize>, start: usize, end: usize) -> bool {
+        !dirty.is_empty() && dirty.range(start..=end).next().is_some()
+    }
+
+    #[inline]
+    pub fn store(
+        comp: &mut IntComp,
+        pc: usize,
+        addr: usize,
+        val: isize,
+    ) -> Result<(), IntcodeError> {
+        comp.mem
+            .set(addr, val)
+            .map_err(|e| fault(comp, pc, e.into()))
+    }
+}
+
+use crate::Instr::{self, *};
+
+fn operands(instr: &Instr) -> Vec<&Arg> {
+    match instr {
+        Add(a, b, c) | Mul(a, b, c) | LessThan(a, b, c) | Equals(a, b, c) => vec![a, b, c],
+        JumpIf(a, b) | JumpUnless(a, b) => vec![a, b],
+        Input(a) | Output(a) | SetRelBase(a) => vec![a],
+        Halt => vec![],
+    }
+}
+
+fn dest(instr: &Instr) -> Option<&Arg> {
+    match instr {
+        Add(.., d) | Mul(.., d) | LessThan(.., d) | Equals(.., d) | Input(d) => Some(d),
+        _ => None,
+    }
+}
+
+/// Reachable instructions, with `None` for addresses that don't decode, and
+/// the ones compiled code can be entered at.
+struct Analysis {
+    instrs: BTreeMap<usize, Option<Instr>>,
+    leaders: BTreeSet<usize>,
+    /// Operand words the program patches through a fixed address, a common
+    /// way to index arrays. Compiled code reads these from memory.
+    dynamic: BTreeSet<usize>,
+    /// Merged `start..=end` ranges of the words compiled code has baked in.
+    fixed: Vec<(usize, usize)>,
+}
+
+impl Analysis {
+    /// The outcome of a conditional jump on a constant.
+    fn static_branch(&self, pc: usize, instr: &Instr) -> Option<bool> {
+        if self.dynamic.contains(&(pc + 1)) {
+            return None;
+        }
+        match instr {
+            JumpIf(Arg::Imm(cond), _) => Some(*cond != 0),
+            JumpUnless(Arg::Imm(cond), _) => Some(*cond == 0),
+            _ => None,
+        }
+    }
+
+    /// Finds code by following control flow from 0.
+    fn discover(&mut self, mem: &Memory) {
+        let mut after_jumps = BTreeSet::new();
+        let mut work = vec![0];
+        self.instrs.clear();
+        self.leaders.clear();
+        self.leaders.insert(0);
+        loop {
+            while let Some(pc) = work.pop() {
+                if self.instrs.contains_key(&pc) || pc >= mem.as_slice().len() {
+                    continue;
+                }
+                let (instr, next) = match decode(mem, pc, 0) {
+                    Ok(decoded) => decoded,
+                    Err(_) => {
+                        self.instrs.insert(pc, None);
+                        continue;
+                    }
+                };
+                match &instr {
+                    Halt => (),
+                    JumpIf(_, target) | JumpUnless(_, target) => {
+                        let taken = self.static_branch(pc, &instr);
+                        if let (Arg::Imm(target), true) = (target, taken != Some(false)) {
+                            if *target >= 0 {
+                                self.leaders.insert(*target as usize);
+                                work.push(*target as usize);
+                            }
+                        }
+                        if taken == Some(true) {
+                            after_jumps.insert(next);
+                        } else {
+                            self.leaders.insert(next);
+                            work.push(next);
+                        }
+                    }
+                    // resume points after returning for I/O.
+                    Output(..) => {
+                        self.leaders.insert(next);
+                        work.push(next);
+                    }
+                    Input(..) => {
+                        self.leaders.insert(pc);
+                        work.push(next);
+                    }
+                    _ => work.push(next),
+                }
+                self.instrs.insert(pc, Some(instr));
+            }
+            // a call pushes the address after its jump as an immediate.
+            let mentioned: BTreeSet<isize> = self
+                .instrs
+                .values()
+                .flatten()
+                .flat_map(operands)
+                .filter_map(|arg| match arg {
+                    Arg::Imm(val) => Some(*val),
+                    _ => None,
+                })
+                .collect();
+            for &ret in &after_jumps {
+                if mentioned.contains(&(ret as isize)) && self.leaders.insert(ret) {
+                    work.push(ret);
+                }
+            }
+            if work.is_empty() {
+                break;
+            }
+        }
+        let instrs = &self.instrs;
+        self.leaders
+            .retain(|pc| matches!(instrs.get(pc), Some(Some(..))));
+    }
+
+    fn code(&self) -> impl Iterator<Item = (usize, &Instr)> {
+        self.instrs
+            .iter()
+            .filter_map(|(pc, instr)| instr.as_ref().map(|instr| (*pc, instr)))
+    }
+
+    fn find_dynamic(&self) -> BTreeSet<usize> {
+        let opcodes: BTreeSet<usize> = self.code().map(|(pc, _)| pc).collect();
+        let operand_words: BTreeSet<usize> = self
+            .code()
+            .flat_map(|(pc, instr)| pc + 1..pc + instr.size())
+            .collect();
+        self.code()
+            .filter_map(|(pc, instr)| match dest(instr) {
+                Some(Arg::Pos(addr))
+                    if *addr >= 0 && !self.dynamic.contains(&(pc + instr.size() - 1)) =>
+                {
+                    Some(*addr as usize)
+                }
+                _ => None,
+            })
+            .filter(|addr| operand_words.contains(addr) && !opcodes.contains(addr))
+            .collect()
+    }
+
+    fn new(mem: &Memory) -> Analysis {
+        let mut analysis = Analysis {
+            instrs: BTreeMap::new(),
+            leaders: BTreeSet::new(),
+            dynamic: BTreeSet::new(),
+            fixed: Vec::new(),
+        };
+        // dynamic operands can turn constant branches into real ones, which
+        // finds more code, which can patch more operands.
+        loop {
+            analysis.discover(mem);
+            let dynamic = analysis.find_dynamic();
+            if dynamic.is_subset(&analysis.dynamic) {
+                break;
+            }
+            analysis.dynamic.extend(dynamic);
+        }
+        let words: BTreeSet<usize> = analysis
+            .code()
+            .flat_map(|(pc, instr)| pc..pc + instr.size())
+            .filter(|word| !analysis.dynamic.contains(word))
+            .collect();
+        for word in words {
+            match analysis.fixed.last_mut() {
+                Some(last) if word == last.1 + 1 => last.1 = word,
+                _ => analysis.fixed.push((word, word)),
+            }
+        }
+        analysis
+    }
+
+    fn is_fixed(&self, addr: usize) -> bool {
+        self.fixed
+            .iter()
+            .any(|(start, end)| (*start..=*end).contains(&addr))
+    }
+
+    /// Whether the interpreter would fault on `instr` no matter what the
+    /// machine state is; compiled code leaves those instructions to it.
+    fn always_faults(&self, pc: usize, instr: &Instr) -> bool {
+        matches!(dest(instr), Some(Arg::Imm(..)))
+            || operands(instr).iter().enumerate().any(|(i, arg)| {
+                matches!(arg, Arg::Pos(addr) if *addr < 0) && !self.dynamic.contains(&(pc + 1 + i))
+            })
+    }
+}
+
+struct Emitter<'a> {
+    out: String,
+    indent: usize,
+    analysis: &'a Analysis,
+}
+
+impl Emitter<'_> {
+    fn line(&mut self, text: &str) {
+        let _ = writeln!(self.out, "{}{}", "    ".repeat(self.indent), text);
+    }
+
+    /// The parameter of `arg`, stored at `word`, if it's a constant.
+    /// Otherwise emits code binding `name` to it.
+    fn param(&mut self, name: &str, arg: &Arg, word: usize) -> Option<isize> {
+        if self.analysis.dynamic.contains(&word) {
+            self.line(&format!("let {} = comp.mem.get({});", name, word));
+            return None;
+        }
+        match arg {
+            Arg::Imm(val) | Arg::Pos(val) | Arg::Rel(val, _) => Some(*val),
+        }
+    }
+
+    /// The address `arg` refers to if it's a constant. Otherwise emits code
+    /// binding `name` to it.
+    fn addr(&mut self, name: &str, arg: &Arg, word: usize, pc: usize) -> Option<usize> {
+        match (arg, self.param(name, arg, word)) {
+            (Arg::Pos(..), Some(addr)) => Some(addr as usize),
+            (Arg::Pos(..), None) => {
+                self.line(&format!("let {} = addr(comp, {}, {})?;", name, pc, name));
+                None
+            }
+            (Arg::Rel(..), param) => {
+                let offset = param.map_or(name.to_string(), |offset| offset.to_string());
+                self.line(&format!("let {} = rel(comp, {}, {})?;", name, pc, offset));
+                None
+            }
+            (Arg::Imm(..), _) => unreachable!("immediates have no address"),
+        }
+    }
+
+    /// Emits code binding `name` to the value of `arg`.
+    fn load(&mut self, name: &str, arg: &Arg, word: usize, pc: usize) {
+        let addr = match arg {
+            Arg::Imm(..) => {
+                if let Some(val) = self.param(name, arg, word) {
+                    self.line(&format!("let {} = {};", name, val));
+                }
+                return;
+            }
+            _ => self.addr(name, arg, word, pc),
+        };
+        let addr = addr.map_or(name.to_string(), |addr| addr.to_string());
+        self.line(&format!("let {} = comp.mem.get({});", name, addr));
+    }
+
+    /// Emits a store of `val` through `dest`, then `after`, then a return
+    /// if the store landed on code.
+    fn store(&mut self, dest: &Arg, word: usize, val: &str, after: &str, pc: usize, next: usize) {
+        let fixed_dest = self.addr("d", dest, word, pc);
+        let addr = fixed_dest.map_or("d".to_string(), |addr| addr.to_string());
+        self.line(&format!("store(comp, {}, {}, {})?;", pc, addr, val));
+        if !after.is_empty() {
+            self.line(after);
+        }
+        match fixed_dest {
+            Some(addr) if self.analysis.is_fixed(addr) => {
+                self.line(&format!("comp.pc = {};", next));
+                self.line(&format!("return Ok(Exit::Wrote({}));", addr));
+            }
+            Some(..) => (),
+            None => {
+                self.line("if is_fixed(d) {");
+                self.line(&format!("    comp.pc = {};", next));
+                self.line("    return Ok(Exit::Wrote(d));");
+                self.line("}");
+            }
+        }
+    }
+
+    fn jump(&mut self, target: &Arg, pc: usize) {
+        let word = pc + 2;
+        match target {
+            Arg::Imm(target) if *target >= 0 && !self.analysis.dynamic.contains(&word) => {
+                self.line(&format!("comp.pc = {};", target))
+            }
+            _ => {
+                self.load("t", target, word, pc);
+                self.line(&format!("comp.pc = addr(comp, {}, t)?;", pc));
+            }
+        }
+        self.line("continue;");
+    }
+
+    /// Emits one instruction, returning false if it ends the block.
+    fn instr(&mut self, pc: usize, instr: &Instr) -> bool {
+        let next = pc + instr.size();
+        self.line(&format!("// {:04}: {}", pc, instr));
+        if self.analysis.always_faults(pc, instr) {
+            self.line(&format!("comp.pc = {};", pc));
+            self.line("return Ok(Exit::Interpret);");
+            return false;
+        }
+        match instr {
+            Add(a, b, d) | Mul(a, b, d) | LessThan(a, b, d) | Equals(a, b, d) => {
+                self.load("a", a, pc + 1, pc);
+                self.load("b", b, pc + 2, pc);
+                let val = match instr {
+                    Add(..) => format!("checked(comp, {}, isize::checked_add(a, b))?", pc),
+                    Mul(..) => format!("checked(comp, {}, isize::checked_mul(a, b))?", pc),
+                    LessThan(..) => "isize::from(a < b)".to_string(),
+                    _ => "isize::from(a == b)".to_string(),
+                };
+                self.line(&format!("let val = {};", val));
+                self.store(d, pc + 3, "val", "", pc, next);
+            }
+            Input(d) => {
+                self.line("let val = match comp.input.front() {");
+                self.line("    Some(&val) => val,");
+                self.line("    None => {");
+                self.line(&format!("        comp.pc = {};", pc));
+                self.line("        return Ok(Exit::Stop(State::NeedsInput));");
+                self.line("    }");
+                self.line("};");
+                // only consumed once the store can't fault.
+                self.store(d, pc + 1, "val", "comp.input.pop_front();", pc, next);
+            }
+            Output(a) => {
+                self.load("a", a, pc + 1, pc);
+                self.line("comp.output.push_back(a);");
+                self.line(&format!("comp.pc = {};", next));
+                self.line("return Ok(Exit::Stop(State::Run));");
+                return false;
+            }
+            JumpIf(a, target) | JumpUnless(a, target) => {
+                match self.analysis.static_branch(pc, instr) {
+                    Some(true) => {
+                        self.jump(target, pc);
+                        return false;
+                    }
+                    Some(false) => (),
+                    None => {
+                        self.load("a", a, pc + 1, pc);
+                        let op = if let JumpIf(..) = instr { "!=" } else { "==" };
+                        self.line(&format!("if a {} 0 {{", op));
+                        self.indent += 1;
+                        self.jump(target, pc);
+                        self.indent -= 1;
+                        self.line("}");
+                        self.line(&format!("comp.pc = {};", next));
+                        self.line("continue;");
+                        return false;
+                    }
+                }
+            }
+            SetRelBase(a) => {
+                self.load("a", a, pc + 1, pc);
+                self.line("let rel_base = isize::checked_add(comp.rel_base, a);");
+                self.line(&format!(
+                    "comp.rel_base = checked(comp, {}, rel_base)?;",
+                    pc
+                ));
+            }
+            Halt => {
+                self.line(&format!("comp.pc = {};", next));
+                self.line("return Ok(Exit::Stop(State::Halt));");
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Emits the block starting at `leader`, returning the last word it
+    /// depends on.
+    fn block(&mut self, leader: usize) -> usize {
+        let mut pc = leader;
+        loop {
+            if pc != leader && self.analysis.leaders.contains(&pc) {
+                self.line(&format!("comp.pc = {};", pc));
+                self.line("continue;");
+                return pc - 1;
+            }
+            let instr = match self.analysis.instrs.get(&pc) {
+                Some(Some(instr)) => instr,
+                _ => {
+                    self.line(&format!("comp.pc = {};", pc));
+                    self.line("return Ok(Exit::Interpret);");
+                    return pc.saturating_sub(1).max(leader);
+                }
+            };
+            let more = self.instr(pc, instr);
+            pc += instr.size();
+            if !more {
+                return pc - 1;
+            }
+        }
+    }
+}
+
+/// Compiles `image` into the source of a module called `name`, which
+/// exports `IMAGE`, `PROGRAM` and `new(input) -> Compiled`.
+pub fn compile(image: &[isize], name: &str) -> String {
+    let mem = Memory::new(image.to_vec());
+    let analysis = Analysis::new(&mem);
+    let mut emitter = Emitter {
+        out: String::new(),
+        indent: 4,
+        analysis: &analysis,
+    };
+    for &leader in &analysis.leaders {
+        let arms = std::mem::take(&mut emitter.out);
+        emitter.indent += 1;
+        let end = emitter.block(leader);
+        emitter.indent -= 1;
+        let body = std::mem::replace(&mut emitter.out, arms);
+        emitter.line(&format!("{} => {{", leader));
+        emitter.line(&format!("    if is_dirty(dirty, {}, {}) {{", leader, end));
+        emitter.line("        return Ok(Exit::Interpret);");
+        emitter.line("    }");
+        emitter.out += &body;
+        emitter.line("}");
+    }
+
+    let ranges: Vec<_> = analysis
+        .fixed
+        .iter()
+        .map(|(start, end)| format!("{}..={}", start, end))
+        .collect();
+    let ranges = if ranges.is_empty() {
+        "false".to_string()
+    } else {
+        format!("matches!(addr, {})", ranges.join(" | "))
+    };
+    let words: Vec<_> = image
+        .chunks(16)
+        .map(|chunk| {
+            let words: Vec<_> = chunk.iter().map(|word| word.to_string()).collect();
+            format!("        {},", words.join(", "))
+        })
+        .collect();
+
+    let mut out = String::new();
+    let _ = write!(
+        out,
+        "// Compiled from an Intcode image by intcode::aot. Do not edit.
+#[allow(unused, unreachable_code, clippy::all)]
+pub mod {name} {{
+    use intcode::aot::rt::{{addr, checked, is_dirty, rel, store}};
+    use intcode::aot::{{Compiled, Exit, Program}};
+    use intcode::{{IntComp, IntcodeError, State}};
+    use std::collections::BTreeSet;
+
+    pub static IMAGE: [isize; {len}] = [
+{words}
+    ];
+
+    pub static PROGRAM: Program = Program {{
+        image: &IMAGE,
+        is_fixed,
+        run,
+    }};
+
+    pub fn new(input: &[isize]) -> Compiled {{
+        Compiled::new(&PROGRAM, IntComp::new(IMAGE.to_vec(), input))
+    }}
+
+    fn is_fixed(addr: usize) -> bool {{
+        {ranges}
+    }}
+
+    fn run(comp: &mut IntComp, dirty: &BTreeSet<usize>) -> Result<Exit, IntcodeError> {{
+        loop {{
+            match comp.pc {{
+{arms}                _ => return Ok(Exit::Interpret),
+            }}
+        }}
+    }}
+}}
+",
+        name = name,
+        len = image.len(),
+        words = words.join("\n"),
+        ranges = ranges,
+        arms = emitter.out,
+    );
+    out
+}
+
+#[test]
+fn test_analyze() {
+    // a call to `double` and back; the word after the call is a return
+    // address, the trailing data isn't code.
+    let mem = crate::asm::assemble(
+        "       arb #stack
+                in -> [rb+0]
+                add #ret, #0 -> [rb+1]
+                jt #1, #double
+         ret:   out [rb+0]
+                hlt
+      double:   mul [rb+0], #2 -> [rb+0]
+                jf #0, [rb+1]
+       stack:   data 0, 0",
+    )
+    .unwrap();
+    let analysis = Analysis::new(&Memory::new(mem));
+    let leaders: Vec<_> = analysis.leaders.iter().copied().collect();
+    assert_eq!(leaders, vec![0, 2, 11, 13, 14]);
+    assert_eq!(analysis.fixed, vec![(0, 20)]);
+    assert!(!analysis.is_fixed(21));
+
+    // indexes an array by patching the operand at 5.
+    let mem = crate::asm::assemble(
+        "       add #arr, [i] -> [load+1]
+         load:  add [0], #0 -> [x]
+                out [x]
+                hlt
+         i:     data 1
+         arr:   data 5, 6
+         x:     data 0",
+    )
+    .unwrap();
+    let src = compile(&mem, "index");
+    assert!(src.contains("pub mod index {"));
+    assert!(src.contains("matches!(addr, 0..=4 | 6..=10)"));
+    assert!(src.contains("// 0004: ADD [0], #0 -> [14]"));
+    assert!(src.contains("let a = comp.mem.get(5);"));
+}
@@ -0,0 +1,62 @@
+//! Picking options out of the day binaries' command lines: flags on their
+//! own, and options followed by a value, which may be given more than once.
+
+use crate::render::Pos;
+use std::fmt;
+
+/// A position that isn't written `x,y`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BadPos(pub String);
+
+impl fmt::Display for BadPos {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "expected x,y but got {:?}", self.0)
+    }
+}
+
+impl std::error::Error for BadPos {}
+
+pub fn flag(args: &[String], name: &str) -> bool {
+    args.iter().any(|arg| arg == name)
+}
+
+/// Every value given for option `name`.
+pub fn values<'a>(args: &'a [String], name: &'a str) -> impl Iterator<Item = &'a String> {
+    args.windows(2)
+        .filter(move |pair| pair[0] == name)
+        .map(|pair| &pair[1])
+}
+
+pub fn value<'a>(args: &'a [String], name: &'a str) -> Option<&'a String> {
+    values(args, name).next()
+}
+
+pub fn parse_pos(s: &str) -> Result<Pos, BadPos> {
+    let mut coords = s.split(',').map(|c| c.trim().parse().ok());
+    match (coords.next(), coords.next(), coords.next()) {
+        (Some(Some(x)), Some(Some(y)), None) => Ok((x, y)),
+        _ => Err(BadPos(s.to_string())),
+    }
+}
+
+/// Every position given for option `name`.
+pub fn positions(args: &[String], name: &str) -> Result<Vec<Pos>, BadPos> {
+    values(args, name).map(|s| parse_pos(s)).collect()
+}
+
+#[test]
+fn test_args() {
+    let args: Vec<String> = ["--live", "--at", "1,2", "--at", " -3, 4 ", "--path", "x"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    assert!(flag(&args, "--live"));
+    assert!(!flag(&args, "--fast"));
+    assert_eq!(value(&args, "--path").map(String::as_str), Some("x"));
+    assert_eq!(value(&args, "--nope"), None);
+    assert_eq!(positions(&args, "--at"), Ok(vec![(1, 2), (-3, 4)]));
+    assert_eq!(positions(&args, "--nope"), Ok(vec![]));
+    for bad in &["1", "1,2,3", "a,2", ""] {
+        assert_eq!(parse_pos(bad), Err(BadPos(bad.to_string())));
+    }
+}
@@ -0,0 +1,128 @@
+//! Talking to programs that speak ASCII, like days 17, 21 and 25: text goes
+//! in a line at a time, and output is split into text and any values too
+//! big to be characters, which is how such programs report their answers.
+
+use crate::{IntComp, IntcodeError, State};
+use std::io::{self, BufRead, Write};
+
+pub struct Console {
+    pub comp: IntComp,
+    text: String,
+    values: Vec<isize>,
+}
+
+impl Console {
+    pub fn new(comp: IntComp) -> Self {
+        Console {
+            comp,
+            text: String::new(),
+            values: Vec::new(),
+        }
+    }
+
+    /// Queues `text` as input, as is.
+    pub fn send(&mut self, text: &str) {
+        self.comp.input.extend(text.bytes().map(isize::from));
+    }
+
+    /// Queues `line` as input followed by a newline.
+    pub fn send_line(&mut self, line: &str) {
+        self.send(line);
+        self.comp.input.push_back(isize::from(b'\n'));
+    }
+
+    /// Runs until the program wants input it doesn't have or halts,
+    /// collecting its output.
+    pub fn run(&mut self) -> Result<State, IntcodeError> {
+        loop {
+            let state = self.comp.run_until_io()?;
+            for val in self.comp.output.drain(..) {
+                match val {
+                    0..=127 => self.text.push(val as u8 as char),
+                    _ => self.values.push(val),
+                }
+            }
+            if state != State::Run {
+                return Ok(state);
+            }
+        }
+    }
+
+    /// The next complete line of output, without its newline.
+    pub fn read_line(&mut self) -> Option<String> {
+        let end = self.text.find('\n')?;
+        let line = self.text[..end].to_string();
+        self.text.drain(..=end);
+        Some(line)
+    }
+
+    /// All the text output not read yet, including any unfinished line.
+    pub fn take_text(&mut self) -> String {
+        std::mem::take(&mut self.text)
+    }
+
+    /// The non-ASCII values output so far.
+    pub fn values(&self) -> &[isize] {
+        &self.values
+    }
+
+    pub fn take_values(&mut self) -> Vec<isize> {
+        std::mem::take(&mut self.values)
+    }
+
+    /// Lets a person drive the program: its text goes to `out`, and each
+    /// line read from `input` is sent to it. Returns when the program
+    /// halts or `input` ends.
+    pub fn play(&mut self, mut input: impl BufRead, mut out: impl Write) -> io::Result<State> {
+        loop {
+            let state = self.run().map_err(io::Error::other)?;
+            write!(out, "{}", self.take_text())?;
+            for val in self.take_values() {
+                writeln!(out, "[{}]", val)?;
+            }
+            out.flush()?;
+            if state == State::Halt {
+                return Ok(state);
+            }
+            let mut line = String::new();
+            if input.read_line(&mut line)? == 0 {
+                return Ok(state);
+            }
+            self.send_line(line.trim_end_matches(['\n', '\r']));
+        }
+    }
+}
+
+#[test]
+fn test_console() {
+    // echoes a line back, then prints its length as a value.
+    let mem = crate::asm::assemble(
+        "loop:  in -> [c]
+                eq [c], #10 -> [t]
+                jt [t], #done
+                out [c]
+                add [n], #1 -> [n]
+                jt #1, #loop
+         done:  out #10
+                add [n], #1000 -> [n]
+                out [n]
+                hlt
+         c:     data 0
+         t:     data 0
+         n:     data 0",
+    )
+    .unwrap();
+    let mut console = Console::new(IntComp::new(mem.clone(), &[]));
+    console.send("hel");
+    assert_eq!(console.run(), Ok(State::NeedsInput));
+    assert_eq!(console.read_line(), None);
+    console.send_line("lo");
+    assert_eq!(console.run(), Ok(State::Halt));
+    assert_eq!(console.read_line(), Some("hello".to_string()));
+    assert_eq!(console.values(), &[1005]);
+
+    let mut console = Console::new(IntComp::new(mem, &[]));
+    let mut out = Vec::new();
+    assert_eq!(console.play(&b"hi\r\n"[..], &mut out).unwrap(), State::Halt);
+    assert_eq!(out, b"hi\n[1002]\n");
+}
@@ -0,0 +1,331 @@
+//! An assembler for the mnemonic syntax printed by `disasm`.
+//!
+//! ```text
+//! ; count down from 5
+//!         arb #counter
+//! loop:   out [rb+0]
+//!         add [rb+0], #-1 -> [rb+0]
+//!         jt [rb+0], #loop
+//!         hlt
+//! counter: data 5
+//! ```
+//!
+//! Operands are `#n` for immediates, `[n]` for positions and `[rb+n]` for
+//! relative addresses, where `n` is a number or a label with an optional
+//! `+`/`-` offset. `data` takes numbers, labels and double-quoted strings,
+//! which are emitted one character per word. Numeric labels such as the
+//! `0012:` prefixes of a listing must match the address they're on.
+
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Debug, PartialEq)]
+pub struct AsmError {
+    pub line: usize,
+    pub msg: String,
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.msg)
+    }
+}
+
+impl std::error::Error for AsmError {}
+
+enum Expr {
+    Num(isize),
+    Label(String, isize),
+}
+
+enum Operand {
+    Imm(Expr),
+    Pos(Expr),
+    Rel(Expr),
+}
+
+enum Item {
+    Instr(isize, Vec<Operand>),
+    Data(Vec<Expr>),
+}
+
+impl Item {
+    fn size(&self) -> usize {
+        match self {
+            Item::Instr(_, args) => args.len() + 1,
+            Item::Data(words) => words.len(),
+        }
+    }
+}
+
+fn opcode(mnemonic: &str) -> Option<(isize, usize)> {
+    Some(match mnemonic {
+        "add" => (1, 3),
+        "mul" => (2, 3),
+        "in" => (3, 1),
+        "out" => (4, 1),
+        "jt" => (5, 2),
+        "jf" => (6, 2),
+        "lt" => (7, 3),
+        "eq" => (8, 3),
+        "arb" => (9, 1),
+        "hlt" => (99, 0),
+        _ => return None,
+    })
+}
+
+fn is_label(s: &str) -> bool {
+    s.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn parse_expr(s: &str) -> Result<Expr, String> {
+    let s = s.trim();
+    if let Ok(val) = s.parse() {
+        return Ok(Expr::Num(val));
+    }
+    let (name, offset) = match s.rfind(['+', '-']) {
+        Some(i) if i > 0 => {
+            let offset = s[i + 1..]
+                .trim()
+                .parse::<isize>()
+                .map_err(|_| format!("bad offset in `{}`", s))?;
+            let sign = if s[i..].starts_with('-') { -1 } else { 1 };
+            (s[..i].trim(), sign * offset)
+        }
+        _ => (s, 0),
+    };
+    if is_label(name) {
+        Ok(Expr::Label(name.to_string(), offset))
+    } else {
+        Err(format!("bad expression `{}`", s))
+    }
+}
+
+fn parse_operand(s: &str) -> Result<Operand, String> {
+    let s = s.trim();
+    if let Some(imm) = s.strip_prefix('#') {
+        Ok(Operand::Imm(parse_expr(imm)?))
+    } else if s.starts_with('[') && s.ends_with(']') {
+        let inner = s[1..s.len() - 1].trim();
+        match inner.strip_prefix("rb") {
+            Some(rel) if rel.trim().is_empty() => Ok(Operand::Rel(Expr::Num(0))),
+            Some(rel) if rel.trim_start().starts_with(['+', '-']) => {
+                let rel = rel.trim_start();
+                let expr = parse_expr(rel.trim_start_matches('+'))?;
+                Ok(Operand::Rel(expr))
+            }
+            _ => Ok(Operand::Pos(parse_expr(inner)?)),
+        }
+    } else {
+        Err(format!("bad operand `{}`", s))
+    }
+}
+
+fn parse_data(s: &str) -> Result<Vec<Expr>, String> {
+    let mut words = Vec::new();
+    let mut chars = s.trim().chars().peekable();
+    while chars.peek().is_some() {
+        if let Some('"') = chars.peek() {
+            chars.next();
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some('\\') => words.push(Expr::Num(match chars.next() {
+                        Some('n') => '\n',
+                        Some('t') => '\t',
+                        Some(c) => c,
+                        None => return Err("unterminated string".to_string()),
+                    } as isize)),
+                    Some(c) => words.push(Expr::Num(c as isize)),
+                    None => return Err("unterminated string".to_string()),
+                }
+            }
+        } else {
+            let word: String = chars.by_ref().take_while(|c| *c != ',').collect();
+            words.push(parse_expr(&word)?);
+            continue;
+        }
+        // skip to the separator after a string.
+        match chars.by_ref().find(|c| !c.is_whitespace()) {
+            None | Some(',') => (),
+            Some(c) => return Err(format!("unexpected `{}` after string", c)),
+        }
+    }
+    Ok(words)
+}
+
+/// Strips a `;` comment, leaving any `;` inside a string alone.
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            ';' if !in_string => return &line[..i],
+            _ => (),
+        }
+    }
+    line
+}
+
+fn parse_line(
+    mut line: &str,
+    addr: usize,
+    labels: &mut HashMap<String, usize>,
+) -> Result<Option<Item>, String> {
+    line = strip_comment(line).trim();
+    while let Some(colon) = line.find(':') {
+        let label = line[..colon].trim();
+        if let Ok(at) = label.parse::<usize>() {
+            if at != addr {
+                return Err(format!("address {} is actually {}", at, addr));
+            }
+        } else if is_label(label) {
+            if labels.insert(label.to_string(), addr).is_some() {
+                return Err(format!("duplicate label `{}`", label));
+            }
+        } else {
+            break;
+        }
+        line = line[colon + 1..].trim();
+    }
+    if line.is_empty() {
+        return Ok(None);
+    }
+    let (mnemonic, rest) = match line.find(char::is_whitespace) {
+        Some(i) => (&line[..i], line[i..].trim()),
+        None => (line, ""),
+    };
+    let mnemonic = mnemonic.to_lowercase();
+    if mnemonic == "data" {
+        return Ok(Some(Item::Data(parse_data(rest)?)));
+    }
+    let (op, arity) =
+        opcode(&mnemonic).ok_or_else(|| format!("unknown mnemonic `{}`", mnemonic))?;
+    let args = if rest.is_empty() {
+        vec![]
+    } else {
+        // `in` has only a destination, which a listing writes as `IN -> [n]`.
+        let rest = rest.strip_prefix("->").unwrap_or(rest);
+        rest.replace("->", ",")
+            .split(',')
+            .map(parse_operand)
+            .collect::<Result<Vec<_>, _>>()?
+    };
+    if args.len() != arity {
+        return Err(format!(
+            "`{}` takes {} operands, got {}",
+            mnemonic,
+            arity,
+            args.len()
+        ));
+    }
+    Ok(Some(Item::Instr(op, args)))
+}
+
+/// Assembles `src` into an Intcode image.
+pub fn assemble(src: &str) -> Result<Vec<isize>, AsmError> {
+    let mut labels = HashMap::new();
+    let mut items = Vec::new();
+    let mut addr = 0;
+    for (i, line) in src.lines().enumerate() {
+        let item =
+            parse_line(line, addr, &mut labels).map_err(|msg| AsmError { line: i + 1, msg })?;
+        if let Some(item) = item {
+            addr += item.size();
+            items.push((i + 1, item));
+        }
+    }
+
+    let mut mem = Vec::with_capacity(addr);
+    for (line, item) in items {
+        let eval = |expr: &Expr| match expr {
+            Expr::Num(val) => Ok(*val),
+            Expr::Label(name, offset) => labels
+                .get(name)
+                .map(|addr| *addr as isize + offset)
+                .ok_or_else(|| AsmError {
+                    line,
+                    msg: format!("unknown label `{}`", name),
+                }),
+        };
+        match item {
+            Item::Instr(op, args) => {
+                let mut word = op;
+                let mut mode_scale = 100;
+                let mut vals = Vec::new();
+                for arg in &args {
+                    let (mode, val) = match arg {
+                        Operand::Pos(expr) => (0, eval(expr)?),
+                        Operand::Imm(expr) => (1, eval(expr)?),
+                        Operand::Rel(expr) => (2, eval(expr)?),
+                    };
+                    word += mode * mode_scale;
+                    mode_scale *= 10;
+                    vals.push(val);
+                }
+                mem.push(word);
+                mem.extend(vals);
+            }
+            Item::Data(words) => {
+                for word in &words {
+                    mem.push(eval(word)?);
+                }
+            }
+        }
+    }
+    Ok(mem)
+}
+
+/// Renders an image in the comma-separated format `parse_program` reads.
+pub fn to_program_text(mem: &[isize]) -> String {
+    let words: Vec<_> = mem.iter().map(|word| word.to_string()).collect();
+    words.join(",") + "\n"
+}
+
+#[test]
+fn test_assemble() {
+    let src = "
+        ; count down from 5
+                arb #counter
+        loop:   out [rb+0]
+                add [rb], #-1 -> [rb+0]
+                jt [rb+0], #loop
+                hlt
+        counter: data 5
+        msg:    data \"hi;\\n\", msg+1
+    ";
+    let mem = assemble(src).unwrap();
+    assert_eq!(
+        mem,
+        vec![109, 12, 204, 0, 21201, 0, -1, 0, 1205, 0, 2, 99, 5, 104, 105, 59, 10, 14]
+    );
+    let mut comp = crate::IntComp::new(mem, &[]);
+    while let crate::State::Run = comp.step().unwrap() {}
+    assert_eq!(comp.output, vec![5, 4, 3, 2, 1]);
+}
+
+#[test]
+fn test_round_trip() {
+    let mem = vec![
+        109, 3, 1001, 104, 5, 104, 1105, 1, 10, 7, 3, 6, 99, 21108, -2, 7, 0,
+    ];
+    let listing = crate::disasm::listing(&crate::Memory::new(mem.clone()), true);
+    assert_eq!(assemble(&listing).unwrap(), mem);
+}
+
+#[test]
+fn test_errors() {
+    assert_eq!(
+        assemble("add #1, #2\n").unwrap_err(),
+        AsmError {
+            line: 1,
+            msg: "`add` takes 3 operands, got 2".to_string()
+        }
+    );
+    assert_eq!(assemble("  jt #1, #nowhere").unwrap_err().line, 1);
+    assert_eq!(assemble("hlt\n0002: hlt").unwrap_err().line, 2);
+}
@@ -0,0 +1,16 @@
+use intcode::asm;
+
+fn main() {
+    let path = std::env::args().nth(1).unwrap_or_else(|| {
+        eprintln!("usage: asm <program.asm>");
+        std::process::exit(2);
+    });
+    let src = std::fs::read_to_string(&path).unwrap();
+    match asm::assemble(&src) {
+        Ok(mem) => print!("{}", asm::to_program_text(&mem)),
+        Err(e) => {
+            eprintln!("{}: {}", path, e);
+            std::process::exit(1);
+        }
+    }
+}
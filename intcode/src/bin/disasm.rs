@@ -0,0 +1,19 @@
+use intcode::{disasm, Memory};
+
+fn main() {
+    let mut annotate = false;
+    let mut path = None;
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "-t" | "--targets" => annotate = true,
+            _ => path = Some(arg),
+        }
+    }
+    let path = path.unwrap_or_else(|| {
+        eprintln!("usage: disasm [--targets] <program.txt>");
+        std::process::exit(2);
+    });
+    let src = std::fs::read_to_string(&path).unwrap();
+    let mem = Memory::new(intcode::parse_program(&src).unwrap());
+    print!("{}", disasm::listing(&mem, annotate));
+}
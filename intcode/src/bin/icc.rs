@@ -0,0 +1,13 @@
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let (path, name) = match (args.next(), args.next()) {
+        (Some(path), name) => (path, name.unwrap_or_else(|| "program".to_string())),
+        _ => {
+            eprintln!("usage: icc <program.txt> [module_name]");
+            std::process::exit(2);
+        }
+    };
+    let src = std::fs::read_to_string(&path).unwrap();
+    let image = intcode::parse_program(&src).unwrap();
+    print!("{}", intcode::aot::compile(&image, &name));
+}
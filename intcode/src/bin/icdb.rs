@@ -0,0 +1,23 @@
+use intcode::debugger::Debugger;
+use intcode::IntComp;
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let path = args.next().unwrap_or_else(|| {
+        eprintln!("usage: icdb <program.txt|snapshot> [input...]");
+        std::process::exit(2);
+    });
+    let input: Vec<isize> = args.map(|arg| arg.parse().unwrap()).collect();
+    let bytes = std::fs::read(&path).unwrap();
+    let mut comp = if bytes.starts_with(b"ICSNAP") {
+        IntComp::restore(&bytes[..]).unwrap()
+    } else {
+        let src = String::from_utf8(bytes).unwrap();
+        IntComp::new(intcode::parse_program(&src).unwrap(), &[])
+    };
+    comp.input.extend(input);
+    let stdin = std::io::stdin();
+    Debugger::new(comp)
+        .repl(stdin.lock(), std::io::stdout())
+        .unwrap();
+}
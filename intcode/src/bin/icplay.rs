@@ -0,0 +1,15 @@
+use intcode::ascii::Console;
+use intcode::IntComp;
+
+fn main() {
+    let path = std::env::args().nth(1).unwrap_or_else(|| {
+        eprintln!("usage: icplay <program.txt>");
+        std::process::exit(2);
+    });
+    let src = std::fs::read_to_string(&path).unwrap();
+    let comp = IntComp::new(intcode::parse_program(&src).unwrap(), &[]);
+    let stdin = std::io::stdin();
+    Console::new(comp)
+        .play(stdin.lock(), std::io::stdout())
+        .unwrap();
+}
@@ -0,0 +1,54 @@
+use intcode::trace::{LogTracer, Profiler};
+use intcode::{IntComp, State};
+
+fn usage() -> ! {
+    eprintln!("usage: icprof [--trace <log>] [--top <n>] <program.txt> [input...]");
+    std::process::exit(2);
+}
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let mut trace_path = None;
+    let mut top = 20;
+    let mut path = None;
+    let mut input = Vec::new();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--trace" => trace_path = Some(args.next().unwrap_or_else(|| usage())),
+            "--top" => {
+                top = args
+                    .next()
+                    .and_then(|n| n.parse().ok())
+                    .unwrap_or_else(|| usage())
+            }
+            _ if path.is_none() => path = Some(arg),
+            _ => input.push(arg.parse::<isize>().unwrap_or_else(|_| usage())),
+        }
+    }
+    let src = std::fs::read_to_string(path.unwrap_or_else(|| usage())).unwrap();
+    let mut comp = IntComp::new(intcode::parse_program(&src).unwrap(), &input);
+
+    let mut profiler = Profiler::new();
+    let state = match trace_path {
+        Some(trace_path) => {
+            let mut tracer = (LogTracer::to_file(trace_path).unwrap(), profiler);
+            let state = loop {
+                match comp.step_with(&mut tracer).unwrap() {
+                    State::Run => continue,
+                    state => break state,
+                }
+            };
+            profiler = tracer.1;
+            state
+        }
+        None => loop {
+            match comp.step_with(&mut profiler).unwrap() {
+                State::Run => continue,
+                state => break state,
+            }
+        },
+    };
+    println!("stopped: {:?}", state);
+    println!("output: {:?}", comp.output);
+    print!("{}", profiler.report(top));
+}
@@ -0,0 +1,19 @@
+use intcode::springscript::{self, Droid, Mode};
+
+fn main() {
+    let path = std::env::args().nth(1).unwrap_or_else(|| {
+        eprintln!("usage: springsynth <program.txt> [max_terms]");
+        std::process::exit(2);
+    });
+    let max_size = std::env::args()
+        .nth(2)
+        .map_or(8, |arg| arg.parse().unwrap());
+    let src = std::fs::read_to_string(&path).unwrap();
+    let droid = Droid::new(intcode::parse_program(&src).unwrap()).unwrap();
+    for &mode in &[Mode::Walk, Mode::Run] {
+        match springscript::synthesize(&droid, mode, max_size).unwrap() {
+            Some((program, damage)) => print!("{}damage: {}\n\n", program, damage),
+            None => println!("no program found for {:?}\n", mode),
+        }
+    }
+}
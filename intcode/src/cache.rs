@@ -0,0 +1,187 @@
+//! The fast path: instructions are decoded once into a compact form and
+//! cached alongside memory until something writes over them, and the run
+//! loop skips the per-step bookkeeping `step_with` does for tracers.
+
+use crate::{jump_target, Fault, IntComp, IntcodeError, Memory, State};
+
+/// A decoded instruction. Unlike `Instr`, relative operands are resolved
+/// when they're used, so one `Op` stays valid as `rel_base` moves.
+#[derive(Clone, Copy)]
+pub(crate) struct Op {
+    code: u8,
+    size: u8,
+    modes: [u8; 3],
+    params: [isize; 3],
+}
+
+fn decode_op(mem: &Memory, pc: usize) -> Result<Op, IntcodeError> {
+    let word = mem.get(pc);
+    let arity = match word % 100 {
+        1 | 2 | 7 | 8 => 3,
+        5 | 6 => 2,
+        3 | 4 | 9 => 1,
+        99 => 0,
+        _ => return Err(IntcodeError::BadOpcode { pc, word }),
+    };
+    let mut op = Op {
+        code: (word % 100) as u8,
+        size: arity as u8 + 1,
+        modes: [0; 3],
+        params: [0; 3],
+    };
+    let mut modes = word / 100;
+    for i in 0..arity {
+        let mode = modes % 10;
+        if mode > 2 {
+            return Err(IntcodeError::BadMode { pc, word, mode });
+        }
+        op.modes[i] = mode as u8;
+        op.params[i] = mem.get(pc + 1 + i);
+        modes /= 10;
+    }
+    Ok(op)
+}
+
+impl Memory {
+    fn op(&mut self, pc: usize) -> Result<Op, IntcodeError> {
+        if let Some(Some(op)) = self.ops.get(pc) {
+            return Ok(*op);
+        }
+        let op = decode_op(self, pc)?;
+        // past the flat part there's nothing worth caching, and a wild jump
+        // shouldn't grow the cache.
+        if pc < self.dense.len() {
+            if pc >= self.ops.len() {
+                self.ops.resize(pc + 1, None);
+            }
+            self.ops[pc] = Some(op);
+        }
+        Ok(op)
+    }
+}
+
+impl IntComp {
+    fn op_addr(&self, op: &Op, i: usize) -> Result<usize, Fault> {
+        let addr = match op.modes[i] {
+            0 => op.params[i],
+            2 => op.params[i]
+                .checked_add(self.rel_base)
+                .ok_or(Fault::Overflow)?,
+            _ => return Err(Fault::ImmediateWrite),
+        };
+        if addr < 0 {
+            Err(Fault::NegativeAddress(addr))
+        } else {
+            Ok(addr as usize)
+        }
+    }
+
+    fn op_load(&self, op: &Op, i: usize) -> Result<isize, Fault> {
+        if op.modes[i] == 1 {
+            Ok(op.params[i])
+        } else {
+            Ok(self.mem.get(self.op_addr(op, i)?))
+        }
+    }
+
+    fn op_store(&mut self, op: &Op, i: usize, val: isize) -> Result<(), Fault> {
+        Ok(self.mem.set(self.op_addr(op, i)?, val)?)
+    }
+
+    fn exec(&mut self, op: &Op) -> Result<State, Fault> {
+        let mut next_pc = self.pc + op.size as usize;
+        match op.code {
+            1 => {
+                let val = self.op_load(op, 0)?.checked_add(self.op_load(op, 1)?);
+                self.op_store(op, 2, val.ok_or(Fault::Overflow)?)?
+            }
+            2 => {
+                let val = self.op_load(op, 0)?.checked_mul(self.op_load(op, 1)?);
+                self.op_store(op, 2, val.ok_or(Fault::Overflow)?)?
+            }
+            3 => match self.input.front() {
+                Some(&val) => {
+                    self.op_store(op, 0, val)?;
+                    self.input.pop_front();
+                }
+                None => return Ok(State::NeedsInput),
+            },
+            4 => self.output.push_back(self.op_load(op, 0)?),
+            5 => {
+                if self.op_load(op, 0)? != 0 {
+                    next_pc = jump_target(self.op_load(op, 1)?)?;
+                }
+            }
+            6 => {
+                if self.op_load(op, 0)? == 0 {
+                    next_pc = jump_target(self.op_load(op, 1)?)?;
+                }
+            }
+            7 => {
+                let val = isize::from(self.op_load(op, 0)? < self.op_load(op, 1)?);
+                self.op_store(op, 2, val)?
+            }
+            8 => {
+                let val = isize::from(self.op_load(op, 0)? == self.op_load(op, 1)?);
+                self.op_store(op, 2, val)?
+            }
+            9 => {
+                let rel_base = self.rel_base.checked_add(self.op_load(op, 0)?);
+                self.rel_base = rel_base.ok_or(Fault::Overflow)?
+            }
+            _ => {
+                self.pc = next_pc;
+                return Ok(State::Halt);
+            }
+        }
+        self.pc = next_pc;
+        Ok(State::Run)
+    }
+
+    /// Runs until the program outputs a value, waits for input or halts,
+    /// returning `State::Run` in the first case. Much faster than looping
+    /// over `step`, but can't be traced. A faulting instruction leaves the
+    /// machine as it was before it.
+    pub fn run_until_io(&mut self) -> Result<State, IntcodeError> {
+        loop {
+            let pc = self.pc;
+            let op = self.mem.op(pc)?;
+            match self.exec(&op) {
+                Ok(State::Run) if op.code == 4 => return Ok(State::Run),
+                Ok(State::Run) => (),
+                Ok(state) => return Ok(state),
+                Err(fault) => return Err(fault.at(pc, self.mem.get(pc))),
+            }
+        }
+    }
+}
+
+#[test]
+fn test_self_modifying() {
+    // counts by patching the immediate operand of its first instruction.
+    let mem = crate::asm::assemble(
+        "start: add #0, #0 -> [x]
+                out [x]
+                add [start+1], #1 -> [start+1]
+                lt [start+1], #3 -> [x]
+                jt [x], #start
+                hlt
+         x:     data 0",
+    )
+    .unwrap();
+    let mut comp = IntComp::new(mem, &[]);
+    for expected in 0..3 {
+        assert_eq!(comp.run_until_io().unwrap(), State::Run);
+        assert_eq!(comp.output.pop_front(), Some(expected));
+    }
+    assert_eq!(comp.run_until_io().unwrap(), State::Halt);
+
+    let mut comp = IntComp::new(vec![3, 2, 1101, 2, 3, 0], &[]);
+    assert_eq!(comp.run_until_io().unwrap(), State::NeedsInput);
+    comp.input.push_back(11101);
+    assert_eq!(
+        comp.run_until_io(),
+        Err(IntcodeError::ImmediateWrite { pc: 2, word: 11101 })
+    );
+    assert_eq!(comp.pc, 2);
+}
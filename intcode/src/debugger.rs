@@ -0,0 +1,336 @@
+//! A line-oriented debugger wrapped around `IntComp::step`.
+
+use crate::disasm::Line;
+use crate::{decode, IntComp, IntcodeError, State};
+use std::collections::BTreeSet;
+use std::io::{self, BufRead, Write};
+
+/// Why the debugger handed control back.
+#[derive(Debug, PartialEq)]
+pub enum Stop {
+    Stepped,
+    Breakpoint(usize),
+    Watchpoint { addr: usize, old: isize, new: isize },
+    NeedsInput,
+    Halt,
+    Error(IntcodeError),
+}
+
+pub struct Debugger {
+    pub comp: IntComp,
+    pub breakpoints: BTreeSet<usize>,
+    pub watchpoints: BTreeSet<usize>,
+}
+
+const HELP: &str = "\
+step [n]            execute n instructions (default 1)
+continue            run until a breakpoint, watchpoint, input wait or halt
+until-input         run until the program waits for input, ignoring breakpoints
+break <addr>        set a breakpoint on pc
+delete <addr>       remove a breakpoint or watchpoint
+watch <addr>        stop when the word at addr changes
+regs                show pc, rel_base and the current instruction
+list [addr] [n]     disassemble n instructions from addr (default pc)
+x <addr> [n]        show n memory words from addr
+poke <addr> <val>.. write words starting at addr
+input <val|\"str\">.. queue input values, strings as ASCII
+io                  show pending input and output
+drain               print and clear the output queue
+save <path>         write a snapshot of the machine
+load <path>         replace the machine with a snapshot
+quit";
+
+enum CmdError {
+    Io(io::Error),
+    Usage(String),
+}
+
+impl From<io::Error> for CmdError {
+    fn from(e: io::Error) -> CmdError {
+        CmdError::Io(e)
+    }
+}
+
+impl From<String> for CmdError {
+    fn from(msg: String) -> CmdError {
+        CmdError::Usage(msg)
+    }
+}
+
+fn parse_num(arg: Option<&str>) -> Result<isize, String> {
+    let arg = arg.ok_or("missing argument")?;
+    arg.parse().map_err(|_| format!("bad number `{}`", arg))
+}
+
+fn parse_addr(arg: Option<&str>) -> Result<usize, String> {
+    let addr = parse_num(arg)?;
+    if addr < 0 {
+        Err(format!("negative address {}", addr))
+    } else {
+        Ok(addr as usize)
+    }
+}
+
+impl Debugger {
+    pub fn new(comp: IntComp) -> Debugger {
+        Debugger {
+            comp,
+            breakpoints: BTreeSet::new(),
+            watchpoints: BTreeSet::new(),
+        }
+    }
+
+    /// Executes a single instruction, reporting anything worth stopping for.
+    pub fn step(&mut self) -> Stop {
+        let watched: Vec<_> = self
+            .watchpoints
+            .iter()
+            .map(|&addr| (addr, self.comp.mem.get(addr)))
+            .collect();
+        match self.comp.step() {
+            Ok(State::Run) => (),
+            Ok(State::NeedsInput) => return Stop::NeedsInput,
+            Ok(State::Halt) => return Stop::Halt,
+            Err(e) => return Stop::Error(e),
+        }
+        for (addr, old) in watched {
+            let new = self.comp.mem.get(addr);
+            if new != old {
+                return Stop::Watchpoint { addr, old, new };
+            }
+        }
+        if self.breakpoints.contains(&self.comp.pc) {
+            return Stop::Breakpoint(self.comp.pc);
+        }
+        Stop::Stepped
+    }
+
+    /// Runs until something stops execution. A breakpoint on the starting pc
+    /// is stepped over rather than reported again.
+    pub fn run(&mut self, honor_breaks: bool) -> Stop {
+        loop {
+            match self.step() {
+                Stop::Stepped => continue,
+                Stop::Breakpoint(..) | Stop::Watchpoint { .. } if !honor_breaks => continue,
+                stop => return stop,
+            }
+        }
+    }
+
+    fn describe(&self, stop: &Stop, out: &mut impl Write) -> io::Result<()> {
+        match stop {
+            Stop::Stepped => (),
+            Stop::Breakpoint(pc) => writeln!(out, "breakpoint at {:04}", pc)?,
+            Stop::Watchpoint { addr, old, new } => {
+                writeln!(out, "watchpoint [{}]: {} -> {}", addr, old, new)?
+            }
+            Stop::NeedsInput => writeln!(out, "waiting for input")?,
+            Stop::Halt => writeln!(out, "halted")?,
+            Stop::Error(e) => writeln!(out, "error: {}", e)?,
+        }
+        self.print_regs(out)
+    }
+
+    fn print_regs(&self, out: &mut impl Write) -> io::Result<()> {
+        writeln!(out, "pc={} rel_base={}", self.comp.pc, self.comp.rel_base)?;
+        self.print_listing(self.comp.pc, 1, out)
+    }
+
+    fn print_listing(&self, mut pc: usize, count: usize, out: &mut impl Write) -> io::Result<()> {
+        for _ in 0..count {
+            let line = match decode(&self.comp.mem, pc, self.comp.rel_base) {
+                Ok((instr, next_pc)) => {
+                    pc = next_pc;
+                    Line::Code(pc - instr.size(), instr)
+                }
+                Err(_) => {
+                    pc += 1;
+                    Line::Data(pc - 1, self.comp.mem.get(pc - 1))
+                }
+            };
+            let marker = if self.breakpoints.contains(&line.addr()) {
+                '*'
+            } else {
+                ' '
+            };
+            writeln!(out, "{}{}", marker, line)?;
+        }
+        Ok(())
+    }
+
+    /// Runs one command line. Returns `Ok(false)` when the user asks to quit.
+    pub fn command(&mut self, line: &str, out: &mut impl Write) -> io::Result<bool> {
+        match self.exec(line, out) {
+            Ok(keep_going) => Ok(keep_going),
+            Err(CmdError::Io(e)) => Err(e),
+            Err(CmdError::Usage(msg)) => writeln!(out, "{}", msg).map(|_| true),
+        }
+    }
+
+    fn exec(&mut self, line: &str, out: &mut impl Write) -> Result<bool, CmdError> {
+        let mut args = line.split_whitespace();
+        let cmd = match args.next() {
+            Some(cmd) => cmd,
+            None => return Ok(true),
+        };
+        let mut opt_addr = |default| args.next().map_or(Ok(default), |a| parse_addr(Some(a)));
+        match cmd {
+            "s" | "step" => {
+                let mut stop = Stop::Stepped;
+                for _ in 0..opt_addr(1)? {
+                    stop = self.step();
+                    if stop != Stop::Stepped {
+                        break;
+                    }
+                }
+                self.describe(&stop, out)?;
+            }
+            "c" | "continue" => {
+                let stop = self.run(true);
+                self.describe(&stop, out)?;
+            }
+            "u" | "until-input" => {
+                let stop = self.run(false);
+                self.describe(&stop, out)?;
+            }
+            "r" | "regs" => self.print_regs(out)?,
+            "l" | "list" => {
+                let pc = opt_addr(self.comp.pc)?;
+                let count = opt_addr(10)?;
+                self.print_listing(pc, count, out)?;
+            }
+            "x" => {
+                let addr = parse_addr(args.next())?;
+                let count = args.next().map_or(Ok(1), |n| parse_addr(Some(n)))?;
+                let words: Vec<_> = (addr..addr + count)
+                    .map(|a| self.comp.mem.get(a).to_string())
+                    .collect();
+                writeln!(out, "[{}]: {}", addr, words.join(" "))?;
+            }
+            "b" | "break" => {
+                self.breakpoints.insert(parse_addr(args.next())?);
+            }
+            "w" | "watch" => {
+                self.watchpoints.insert(parse_addr(args.next())?);
+            }
+            "d" | "delete" => {
+                let addr = parse_addr(args.next())?;
+                if !self.breakpoints.remove(&addr) && !self.watchpoints.remove(&addr) {
+                    return Err(format!("nothing set at {}", addr).into());
+                }
+            }
+            "poke" => {
+                let addr = parse_addr(args.next())?;
+                for (i, val) in args.enumerate() {
+                    let val = parse_num(Some(val))?;
+                    self.comp
+                        .mem
+                        .set(addr + i, val)
+                        .map_err(|e| e.to_string())?;
+                }
+            }
+            "i" | "input" => {
+                let rest = line.trim_start()[cmd.len()..].trim();
+                if let Some(text) = rest.strip_prefix('"') {
+                    let text = text.strip_suffix('"').unwrap_or(text).replace("\\n", "\n");
+                    self.comp.input.extend(text.chars().map(|c| c as isize));
+                } else {
+                    for val in args {
+                        self.comp.input.push_back(parse_num(Some(val))?);
+                    }
+                }
+            }
+            "io" => {
+                writeln!(out, "input:  {:?}", self.comp.input)?;
+                writeln!(out, "output: {:?}", self.comp.output)?;
+            }
+            "drain" => {
+                let output: Vec<_> = self.comp.output.drain(..).collect();
+                let text: Option<String> = output
+                    .iter()
+                    .map(|&c| {
+                        if (0..128).contains(&c) {
+                            Some(c as u8 as char)
+                        } else {
+                            None
+                        }
+                    })
+                    .collect();
+                match text {
+                    Some(text) => write!(out, "{}", text)?,
+                    None => writeln!(out, "{:?}", output)?,
+                }
+            }
+            "save" => {
+                let path = args.next().ok_or_else(|| "missing path".to_string())?;
+                self.comp.save_to(path)?;
+            }
+            "load" => {
+                let path = args.next().ok_or_else(|| "missing path".to_string())?;
+                self.comp = IntComp::restore_from(path)?;
+                self.print_regs(out)?;
+            }
+            "h" | "help" => writeln!(out, "{}", HELP)?,
+            "q" | "quit" => return Ok(false),
+            _ => return Err(format!("unknown command `{}`, try `help`", cmd).into()),
+        }
+        Ok(true)
+    }
+
+    /// Reads commands until `quit` or end of input.
+    pub fn repl(&mut self, input: impl BufRead, mut out: impl Write) -> io::Result<()> {
+        self.print_regs(&mut out)?;
+        write!(out, "(icdb) ")?;
+        out.flush()?;
+        for line in input.lines() {
+            if !self.command(&line?, &mut out)? {
+                break;
+            }
+            write!(out, "(icdb) ")?;
+            out.flush()?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_break_and_watch() {
+    // counts [rb+0] down from 3, then asks for input.
+    let mem = crate::asm::assemble(
+        "       arb #counter
+         loop:  add [rb], #-1 -> [rb]
+                jt [rb], #loop
+                in -> [rb]
+                hlt
+      counter:  data 3",
+    )
+    .unwrap();
+    let mut dbg = Debugger::new(IntComp::new(mem, &[]));
+    dbg.breakpoints.insert(6);
+    assert_eq!(dbg.run(true), Stop::Breakpoint(6));
+    dbg.watchpoints.insert(12);
+    assert_eq!(
+        dbg.run(true),
+        Stop::Watchpoint {
+            addr: 12,
+            old: 2,
+            new: 1
+        }
+    );
+    assert_eq!(dbg.run(false), Stop::NeedsInput);
+
+    let mut out = Vec::new();
+    for cmd in &["input 7", "x 12", "c", "bogus"] {
+        assert!(dbg.command(cmd, &mut out).unwrap());
+    }
+    assert!(!dbg.command("quit", &mut out).unwrap());
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "[12]: 0
+watchpoint [12]: 0 -> 7
+pc=11 rel_base=12
+ 0011: HLT
+unknown command `bogus`, try `help`
+"
+    );
+}
@@ -0,0 +1,190 @@
+use crate::{decode, Arg, Instr, Memory};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+impl fmt::Display for Arg {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Arg::Imm(val) => write!(f, "#{}", val),
+            Arg::Pos(addr) => write!(f, "[{}]", addr),
+            Arg::Rel(offset, _) if *offset < 0 => write!(f, "[rb{}]", offset),
+            Arg::Rel(offset, _) => write!(f, "[rb+{}]", offset),
+        }
+    }
+}
+
+impl Instr {
+    pub fn mnemonic(&self) -> &'static str {
+        use Instr::*;
+        match self {
+            Add(..) => "add",
+            Mul(..) => "mul",
+            Input(..) => "in",
+            Output(..) => "out",
+            JumpIf(..) => "jt",
+            JumpUnless(..) => "jf",
+            LessThan(..) => "lt",
+            Equals(..) => "eq",
+            SetRelBase(..) => "arb",
+            Halt => "hlt",
+        }
+    }
+
+    /// The number of words the instruction occupies, opcode included.
+    pub fn size(&self) -> usize {
+        use Instr::*;
+        match self {
+            Add(..) | Mul(..) | LessThan(..) | Equals(..) => 4,
+            JumpIf(..) | JumpUnless(..) => 3,
+            Input(..) | Output(..) | SetRelBase(..) => 2,
+            Halt => 1,
+        }
+    }
+}
+
+impl fmt::Display for Instr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use Instr::*;
+        let name = self.mnemonic().to_uppercase();
+        match self {
+            Add(a, b, dest) | Mul(a, b, dest) | LessThan(a, b, dest) | Equals(a, b, dest) => {
+                write!(f, "{} {}, {} -> {}", name, a, b, dest)
+            }
+            Input(dest) => write!(f, "{} -> {}", name, dest),
+            Output(a) | SetRelBase(a) => write!(f, "{} {}", name, a),
+            JumpIf(a, target) | JumpUnless(a, target) => write!(f, "{} {}, {}", name, a, target),
+            Halt => write!(f, "{}", name),
+        }
+    }
+}
+
+/// One line of a disassembly: either a decoded instruction or a word which
+/// didn't decode.
+pub enum Line {
+    Code(usize, Instr),
+    Data(usize, isize),
+}
+
+impl Line {
+    pub fn addr(&self) -> usize {
+        match self {
+            Line::Code(addr, _) | Line::Data(addr, _) => *addr,
+        }
+    }
+}
+
+impl fmt::Display for Line {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Line::Code(addr, instr) => write!(f, "{:04}: {}", addr, instr),
+            Line::Data(addr, val) => write!(f, "{:04}: DATA {}", addr, val),
+        }
+    }
+}
+
+/// Sweeps the image from address 0, decoding instructions back to back.
+/// Words which don't decode, carry mode digits beyond their operands, or
+/// would run into one of `code_starts` are emitted as data.
+pub fn disassemble(mem: &Memory, code_starts: &BTreeSet<usize>) -> Vec<Line> {
+    let end = mem.as_slice().len();
+    let mut lines = Vec::new();
+    let mut pc = 0;
+    while pc < end {
+        match decode(mem, pc, 0) {
+            Ok((instr, next_pc))
+                if next_pc <= end
+                    && mem.get(pc) / 10isize.pow(instr.size() as u32 + 1) == 0
+                    && code_starts.range(pc + 1..next_pc).next().is_none() =>
+            {
+                lines.push(Line::Code(pc, instr));
+                pc = next_pc;
+            }
+            _ => {
+                lines.push(Line::Data(pc, mem.get(pc)));
+                pc += 1;
+            }
+        }
+    }
+    lines
+}
+
+/// Follows control flow from address 0 through fallthroughs and jumps with
+/// immediate targets. Returns each jump target with the addresses that jump
+/// to it.
+pub fn jump_targets(mem: &Memory) -> BTreeMap<usize, BTreeSet<usize>> {
+    let end = mem.as_slice().len();
+    let mut targets: BTreeMap<usize, BTreeSet<usize>> = BTreeMap::new();
+    let mut seen = BTreeSet::new();
+    let mut tasks = vec![0];
+    while let Some(pc) = tasks.pop() {
+        if pc >= end || !seen.insert(pc) {
+            continue;
+        }
+        let (instr, next_pc) = match decode(mem, pc, 0) {
+            Ok(decoded) => decoded,
+            Err(_) => continue,
+        };
+        let (jump_when, cond, target) = match &instr {
+            Instr::Halt => continue,
+            Instr::JumpIf(cond, target) => (true, cond, target),
+            Instr::JumpUnless(cond, target) => (false, cond, target),
+            _ => {
+                tasks.push(next_pc);
+                continue;
+            }
+        };
+        // an immediate condition decides the branch statically.
+        let (may_jump, may_fall) = match cond {
+            Arg::Imm(val) => ((*val != 0) == jump_when, (*val != 0) != jump_when),
+            _ => (true, true),
+        };
+        if let (true, Arg::Imm(target)) = (may_jump, target) {
+            if *target >= 0 {
+                let target = *target as usize;
+                targets.entry(target).or_default().insert(pc);
+                tasks.push(target);
+            }
+        }
+        if may_fall {
+            tasks.push(next_pc);
+        }
+    }
+    targets
+}
+
+/// Renders a listing. With `annotate`, jump targets found by `jump_targets`
+/// are marked with the addresses that reach them and keep the sweep aligned.
+pub fn listing(mem: &Memory, annotate: bool) -> String {
+    use std::fmt::Write;
+    let targets = if annotate {
+        jump_targets(mem)
+    } else {
+        BTreeMap::new()
+    };
+    let code_starts = targets.keys().cloned().collect();
+    let mut out = String::new();
+    for line in disassemble(mem, &code_starts) {
+        if let Some(sources) = targets.get(&line.addr()) {
+            let sources: Vec<_> = sources.iter().map(|src| format!("{:04}", src)).collect();
+            let _ = writeln!(&mut out, "      ; from {}", sources.join(", "));
+        }
+        let _ = writeln!(&mut out, "{}", line);
+    }
+    out
+}
+
+#[test]
+fn test_listing() {
+    let mem = Memory::new(vec![109, 3, 1001, 104, 5, 104, 1105, 1, 10, 7, 3, 6, 99]);
+    assert_eq!(
+        listing(&mem, true),
+        "0000: ARB #3
+0002: ADD [104], #5 -> [104]
+0006: JT #1, #10
+0009: DATA 7
+      ; from 0006
+0010: IN -> [6]
+0012: HLT
+"
+    );
+}
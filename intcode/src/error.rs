@@ -0,0 +1,113 @@
+use crate::LimitExceeded;
+use std::fmt;
+
+/// A fault raised while decoding or executing the instruction at `pc`.
+/// `word` is the raw instruction word found there.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IntcodeError {
+    BadOpcode {
+        pc: usize,
+        word: isize,
+    },
+    BadMode {
+        pc: usize,
+        word: isize,
+        mode: isize,
+    },
+    ImmediateWrite {
+        pc: usize,
+        word: isize,
+    },
+    NegativeAddress {
+        pc: usize,
+        word: isize,
+        addr: isize,
+    },
+    OutOfRange {
+        pc: usize,
+        word: isize,
+        addr: usize,
+        limit: usize,
+    },
+    /// Arithmetic on a value, an address or the relative base overflowed.
+    Overflow {
+        pc: usize,
+        word: isize,
+    },
+}
+
+impl IntcodeError {
+    pub fn pc(&self) -> usize {
+        use IntcodeError::*;
+        match *self {
+            BadOpcode { pc, .. }
+            | BadMode { pc, .. }
+            | ImmediateWrite { pc, .. }
+            | NegativeAddress { pc, .. }
+            | OutOfRange { pc, .. }
+            | Overflow { pc, .. } => pc,
+        }
+    }
+
+    pub fn word(&self) -> isize {
+        use IntcodeError::*;
+        match *self {
+            BadOpcode { word, .. }
+            | BadMode { word, .. }
+            | ImmediateWrite { word, .. }
+            | NegativeAddress { word, .. }
+            | OutOfRange { word, .. }
+            | Overflow { word, .. } => word,
+        }
+    }
+}
+
+impl fmt::Display for IntcodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use IntcodeError::*;
+        match self {
+            BadOpcode { .. } => write!(f, "unknown opcode {}", self.word() % 100),
+            BadMode { mode, .. } => write!(f, "unknown mode {}", mode),
+            ImmediateWrite { .. } => write!(f, "can't store to an immediate"),
+            NegativeAddress { addr, .. } => write!(f, "negative address {}", addr),
+            OutOfRange { addr, limit, .. } => {
+                write!(f, "address {} is beyond the limit of {}", addr, limit)
+            }
+            Overflow { .. } => write!(f, "arithmetic overflow"),
+        }?;
+        write!(f, " at pc {} (instruction {})", self.pc(), self.word())
+    }
+}
+
+impl std::error::Error for IntcodeError {}
+
+/// An execution fault which hasn't yet been pinned to an instruction.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Fault {
+    ImmediateWrite,
+    NegativeAddress(isize),
+    OutOfRange(LimitExceeded),
+    Overflow,
+}
+
+impl Fault {
+    pub fn at(self, pc: usize, word: isize) -> IntcodeError {
+        match self {
+            Fault::ImmediateWrite => IntcodeError::ImmediateWrite { pc, word },
+            Fault::NegativeAddress(addr) => IntcodeError::NegativeAddress { pc, word, addr },
+            Fault::OutOfRange(LimitExceeded { addr, limit }) => IntcodeError::OutOfRange {
+                pc,
+                word,
+                addr,
+                limit,
+            },
+            Fault::Overflow => IntcodeError::Overflow { pc, word },
+        }
+    }
+}
+
+impl From<LimitExceeded> for Fault {
+    fn from(e: LimitExceeded) -> Fault {
+        Fault::OutOfRange(e)
+    }
+}
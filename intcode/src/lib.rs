@@ -0,0 +1,343 @@
+//! The Intcode computer shared by the day binaries.
+
+pub mod amp;
+pub mod aot;
+pub mod args;
+pub mod ascii;
+pub mod asm;
+mod cache;
+pub mod debugger;
+pub mod disasm;
+mod error;
+mod mem;
+pub mod network;
+pub mod pipe;
+pub mod render;
+mod snapshot;
+pub mod springscript;
+pub mod trace;
+
+pub use error::{Fault, IntcodeError};
+pub use mem::{LimitExceeded, Memory, DEFAULT_LIMIT};
+use std::collections::VecDeque;
+pub use trace::Tracer;
+
+#[derive(Clone)]
+pub struct IntComp {
+    pub mem: Memory,
+    pub input: VecDeque<isize>,
+    pub output: VecDeque<isize>,
+    pub pc: usize,
+    pub rel_base: isize,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum State {
+    Run,
+    NeedsInput,
+    Halt,
+}
+
+pub enum Arg {
+    Imm(isize),
+    Pos(isize),
+    Rel(isize, isize),
+}
+
+impl Arg {
+    fn addr(&self) -> Result<usize, Fault> {
+        let addr = match self {
+            Arg::Pos(pos) => *pos,
+            Arg::Rel(pos, rel_base) => pos.checked_add(*rel_base).ok_or(Fault::Overflow)?,
+            Arg::Imm(..) => return Err(Fault::ImmediateWrite),
+        };
+        if addr < 0 {
+            Err(Fault::NegativeAddress(addr))
+        } else {
+            Ok(addr as usize)
+        }
+    }
+
+    pub fn load(&self, mem: &Memory) -> Result<isize, Fault> {
+        match self {
+            Arg::Imm(val) => Ok(*val),
+            _ => Ok(mem.get(self.addr()?)),
+        }
+    }
+
+    pub fn store(&self, val: isize, mem: &mut Memory) -> Result<(), Fault> {
+        Ok(mem.set(self.addr()?, val)?)
+    }
+}
+
+pub enum Instr {
+    Add(Arg, Arg, Arg),
+    Mul(Arg, Arg, Arg),
+    Input(Arg),
+    Output(Arg),
+    JumpIf(Arg, Arg),
+    JumpUnless(Arg, Arg),
+    LessThan(Arg, Arg, Arg),
+    Equals(Arg, Arg, Arg),
+    SetRelBase(Arg),
+    Halt,
+}
+
+fn jump_target(target: isize) -> Result<usize, Fault> {
+    if target < 0 {
+        Err(Fault::NegativeAddress(target))
+    } else {
+        Ok(target as usize)
+    }
+}
+
+impl Instr {
+    /// Executes the instruction, moving `comp.pc` to `next_pc` unless it
+    /// jumps. An input instruction with nothing queued, or an instruction
+    /// that faults, leaves `comp` untouched; the former returns
+    /// `State::NeedsInput`.
+    pub fn execute(&self, comp: &mut IntComp, next_pc: usize) -> Result<State, Fault> {
+        use Instr::*;
+        let val = match self {
+            Input(..) => match comp.input.front() {
+                Some(&val) => val,
+                None => return Ok(State::NeedsInput),
+            },
+            _ => 0,
+        };
+        let mut pc = next_pc;
+        match self {
+            Add(arg1, arg2, dest) => {
+                let val = arg1.load(&comp.mem)?.checked_add(arg2.load(&comp.mem)?);
+                dest.store(val.ok_or(Fault::Overflow)?, &mut comp.mem)?
+            }
+            Mul(arg1, arg2, dest) => {
+                let val = arg1.load(&comp.mem)?.checked_mul(arg2.load(&comp.mem)?);
+                dest.store(val.ok_or(Fault::Overflow)?, &mut comp.mem)?
+            }
+            Input(arg) => arg.store(val, &mut comp.mem)?,
+            Output(arg) => comp.output.push_back(arg.load(&comp.mem)?),
+            JumpIf(arg, new_pc) => {
+                if arg.load(&comp.mem)? != 0 {
+                    pc = jump_target(new_pc.load(&comp.mem)?)?;
+                }
+            }
+            JumpUnless(arg, new_pc) => {
+                if arg.load(&comp.mem)? == 0 {
+                    pc = jump_target(new_pc.load(&comp.mem)?)?;
+                }
+            }
+            LessThan(arg1, arg2, dest) => dest.store(
+                if arg1.load(&comp.mem)? < arg2.load(&comp.mem)? {
+                    1
+                } else {
+                    0
+                },
+                &mut comp.mem,
+            )?,
+            Equals(arg1, arg2, dest) => dest.store(
+                if arg1.load(&comp.mem)? == arg2.load(&comp.mem)? {
+                    1
+                } else {
+                    0
+                },
+                &mut comp.mem,
+            )?,
+            SetRelBase(arg) => {
+                let rel_base = comp.rel_base.checked_add(arg.load(&comp.mem)?);
+                comp.rel_base = rel_base.ok_or(Fault::Overflow)?
+            }
+            Halt => (),
+        };
+        comp.pc = pc;
+        if let Input(..) = self {
+            comp.input.pop_front();
+        }
+
+        if let Halt = self {
+            Ok(State::Halt)
+        } else {
+            Ok(State::Run)
+        }
+    }
+}
+
+/// Decodes the instruction at `pc`, returning it and the address of the one
+/// after it.
+pub fn decode(mem: &Memory, pc: usize, rel_base: isize) -> Result<(Instr, usize), IntcodeError> {
+    use Instr::*;
+    let start_pc = pc;
+    let mut pc = pc;
+    let instr = mem.get(pc);
+    pc += 1;
+    // this closure will increment pc and shift mode.
+    let mut mode = instr / 100;
+    let pc_ref = &mut pc;
+    let mut make_arg = move || {
+        let val = mem.get(*pc_ref);
+        *pc_ref += 1;
+        let ret = match mode % 10 {
+            0 => Arg::Pos(val),
+            1 => Arg::Imm(val),
+            2 => Arg::Rel(val, rel_base),
+            i => {
+                return Err(IntcodeError::BadMode {
+                    pc: start_pc,
+                    word: instr,
+                    mode: i,
+                })
+            }
+        };
+        mode /= 10;
+        Ok(ret)
+    };
+
+    let instr = match instr % 100 {
+        1 => Add(make_arg()?, make_arg()?, make_arg()?),
+        2 => Mul(make_arg()?, make_arg()?, make_arg()?),
+        3 => Input(make_arg()?),
+        4 => Output(make_arg()?),
+        5 => JumpIf(make_arg()?, make_arg()?),
+        6 => JumpUnless(make_arg()?, make_arg()?),
+        7 => LessThan(make_arg()?, make_arg()?, make_arg()?),
+        8 => Equals(make_arg()?, make_arg()?, make_arg()?),
+        9 => SetRelBase(make_arg()?),
+        99 => Halt,
+        _ => {
+            return Err(IntcodeError::BadOpcode {
+                pc: start_pc,
+                word: instr,
+            })
+        }
+    };
+    Ok((instr, pc))
+}
+
+impl IntComp {
+    pub fn new(mem: impl Into<Memory>, input: &[isize]) -> IntComp {
+        IntComp {
+            mem: mem.into(),
+            input: input.iter().cloned().collect(),
+            output: VecDeque::new(),
+            pc: 0,
+            rel_base: 0,
+        }
+    }
+    pub fn decode(&self) -> Result<(Instr, usize), IntcodeError> {
+        decode(&self.mem, self.pc, self.rel_base)
+    }
+    pub fn step(&mut self) -> Result<State, IntcodeError> {
+        self.step_with(&mut ())
+    }
+    /// Like `step`, but reports the executed instruction and any I/O to
+    /// `tracer`.
+    pub fn step_with(&mut self, tracer: &mut impl Tracer) -> Result<State, IntcodeError> {
+        let (pc, word) = (self.pc, self.mem.get(self.pc));
+        let (instr, new_pc) = self.decode()?;
+        let input = match instr {
+            Instr::Input(..) => match self.input.front() {
+                Some(&val) => Some(val),
+                None => return Ok(State::NeedsInput),
+            },
+            _ => None,
+        };
+        tracer.instr(pc, &instr, self);
+        let state = instr
+            .execute(self, new_pc)
+            .map_err(|fault| fault.at(pc, word))?;
+        if let Some(val) = input {
+            tracer.input(val);
+        } else if let (Instr::Output(..), Some(&val)) = (&instr, self.output.back()) {
+            tracer.output(val);
+        }
+        Ok(state)
+    }
+}
+
+/// Parses an Intcode image in the comma-separated puzzle input format.
+pub fn parse_program(src: &str) -> Result<Vec<isize>, std::num::ParseIntError> {
+    src.trim().split(',').map(|s| s.trim().parse()).collect()
+}
+
+#[test]
+fn test_parse_program() {
+    assert_eq!(parse_program("1,0, 0,3,99\n"), Ok(vec![1, 0, 0, 3, 99]));
+    assert!(parse_program("1,x,99").is_err());
+}
+
+#[test]
+fn test_quine() {
+    let prog = "109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99";
+    let mem = parse_program(prog).unwrap();
+    let expected = mem.clone();
+    let mut comp = IntComp::new(mem, &[]);
+    while let State::Run = comp.step().unwrap() {}
+    assert_eq!(comp.output, expected);
+}
+
+#[test]
+fn test_io_compare() {
+    // outputs 1 if the input equals 8, else 0.
+    let mem = parse_program("3,9,8,9,10,9,4,9,99,-1,8").unwrap();
+    for (input, expected) in &[(8, 1), (7, 0)] {
+        let mut comp = IntComp::new(mem.clone(), &[*input]);
+        while let State::Run = comp.step().unwrap() {}
+        assert_eq!(comp.output.pop_front(), Some(*expected));
+    }
+}
+
+#[test]
+fn test_errors() {
+    let run = |prog: Vec<isize>| {
+        let mut comp = IntComp::new(Memory::new(prog).with_limit(100), &[]);
+        loop {
+            match comp.step() {
+                Ok(State::Run) => continue,
+                Ok(state) => panic!("unexpected {:?}", state),
+                Err(e) => break e,
+            }
+        }
+    };
+    use IntcodeError::*;
+    assert_eq!(run(vec![1, 0, 0, 0, 42]), BadOpcode { pc: 4, word: 42 });
+    assert_eq!(
+        run(vec![301, 0, 0, 0]),
+        BadMode {
+            pc: 0,
+            word: 301,
+            mode: 3
+        }
+    );
+    assert_eq!(
+        run(vec![11101, 1, 1, 0]),
+        ImmediateWrite { pc: 0, word: 11101 }
+    );
+    assert_eq!(
+        run(vec![109, -5, 2201, 0, 0, 0]),
+        NegativeAddress {
+            pc: 2,
+            word: 2201,
+            addr: -5
+        }
+    );
+    assert_eq!(
+        run(vec![1101, 1, 1, 100]),
+        OutOfRange {
+            pc: 0,
+            word: 1101,
+            addr: 100,
+            limit: 100
+        }
+    );
+    let max = isize::MAX;
+    assert_eq!(run(vec![1101, max, 1, 0]), Overflow { pc: 0, word: 1101 });
+    assert_eq!(run(vec![1102, max, 2, 0]), Overflow { pc: 0, word: 1102 });
+    assert_eq!(run(vec![109, max, 109, 1]), Overflow { pc: 2, word: 109 });
+    assert_eq!(
+        run(vec![109, 1, 2201, max, 0, 0]),
+        Overflow { pc: 2, word: 2201 }
+    );
+    // the fast path agrees.
+    let mut comp = IntComp::new(vec![109, 1, 1201, 5, max, 0, max], &[]);
+    assert_eq!(comp.run_until_io(), Err(Overflow { pc: 2, word: 1201 }));
+}
@@ -0,0 +1,156 @@
+use crate::cache::Op;
+use std::collections::HashMap;
+use std::fmt;
+
+pub(crate) const PAGE_SIZE: usize = 1024;
+
+/// Default ceiling on addressable memory, in words.
+pub const DEFAULT_LIMIT: usize = 1 << 24;
+
+/// Intcode memory which grows on writes and reads as zero past the end.
+///
+/// Addresses below `sparse_from` (all of them, by default) live in a flat
+/// `Vec`. Addresses at or above it go into a page map, so a program poking at
+/// a very high address doesn't allocate everything in between.
+#[derive(Clone)]
+pub struct Memory {
+    pub(crate) dense: Vec<isize>,
+    pub(crate) pages: HashMap<usize, Box<[isize]>>,
+    pub(crate) sparse_from: usize,
+    pub(crate) limit: usize,
+    /// Instructions decoded by `IntComp::run_until_io`, indexed by address.
+    pub(crate) ops: Vec<Option<Op>>,
+}
+
+/// A write landed at or beyond the memory's configured limit.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LimitExceeded {
+    pub addr: usize,
+    pub limit: usize,
+}
+
+impl fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "write to {} exceeds memory limit of {} words",
+            self.addr, self.limit
+        )
+    }
+}
+
+impl std::error::Error for LimitExceeded {}
+
+impl Memory {
+    pub fn new(image: Vec<isize>) -> Memory {
+        Memory {
+            dense: image,
+            pages: HashMap::new(),
+            sparse_from: usize::MAX,
+            limit: DEFAULT_LIMIT,
+            ops: Vec::new(),
+        }
+    }
+
+    /// Sets the hard ceiling: writes at or above `limit` fail.
+    pub fn with_limit(mut self, limit: usize) -> Memory {
+        self.limit = limit;
+        self
+    }
+
+    /// Stores addresses at or above `addr` in sparse pages instead of
+    /// growing the flat part of memory.
+    pub fn sparse_above(mut self, addr: usize) -> Memory {
+        for (i, val) in self.dense.iter().enumerate().skip(addr) {
+            if *val != 0 {
+                self.pages
+                    .entry(i / PAGE_SIZE)
+                    .or_insert_with(|| vec![0; PAGE_SIZE].into_boxed_slice())[i % PAGE_SIZE] = *val;
+            }
+        }
+        self.dense.truncate(addr);
+        self.sparse_from = addr;
+        self.ops.clear();
+        self
+    }
+
+    #[inline]
+    pub fn get(&self, addr: usize) -> isize {
+        if addr >= self.sparse_from {
+            self.pages
+                .get(&(addr / PAGE_SIZE))
+                .map_or(0, |page| page[addr % PAGE_SIZE])
+        } else {
+            self.dense.get(addr).copied().unwrap_or(0)
+        }
+    }
+
+    #[inline]
+    pub fn set(&mut self, addr: usize, val: isize) -> Result<(), LimitExceeded> {
+        if addr >= self.limit {
+            return Err(LimitExceeded {
+                addr,
+                limit: self.limit,
+            });
+        }
+        if addr >= self.sparse_from {
+            self.pages
+                .entry(addr / PAGE_SIZE)
+                .or_insert_with(|| vec![0; PAGE_SIZE].into_boxed_slice())[addr % PAGE_SIZE] = val;
+        } else {
+            if addr >= self.dense.len() {
+                self.dense.resize(addr + 1, 0);
+            }
+            self.dense[addr] = val;
+        }
+        // drop any cached instruction this word is part of.
+        let end = (addr + 1).min(self.ops.len());
+        for op in &mut self.ops[addr.saturating_sub(3).min(end)..end] {
+            *op = None;
+        }
+        Ok(())
+    }
+
+    /// The flat part of memory, starting at address 0.
+    pub fn as_slice(&self) -> &[isize] {
+        &self.dense
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+}
+
+impl From<Vec<isize>> for Memory {
+    fn from(image: Vec<isize>) -> Memory {
+        Memory::new(image)
+    }
+}
+
+#[test]
+fn test_grow_on_demand() {
+    let mut mem = Memory::new(vec![1, 2, 3]);
+    assert_eq!(mem.get(5000), 0);
+    mem.set(5000, 7).unwrap();
+    assert_eq!(mem.get(5000), 7);
+    assert_eq!(mem.get(4999), 0);
+    assert_eq!(mem.as_slice().len(), 5001);
+}
+
+#[test]
+fn test_sparse_and_limit() {
+    let mut mem = Memory::new(vec![1, 2, 3])
+        .sparse_above(2)
+        .with_limit(1 << 40);
+    assert_eq!(mem.get(2), 3);
+    mem.set(1 << 35, -4).unwrap();
+    assert_eq!(mem.get(1 << 35), -4);
+    assert_eq!(mem.as_slice(), &[1, 2]);
+    assert_eq!(
+        mem.set(1 << 40, 1),
+        Err(LimitExceeded {
+            addr: 1 << 40,
+            limit: 1 << 40
+        })
+    );
+}
@@ -0,0 +1,521 @@
+//! Several machines run in one thread, wired together by how each one's
+//! output is framed: a plain stream into another machine's input, or
+//! packets that name their destination, as in day 23.
+//!
+//! ```text
+//! let mut net = Network::new(Schedule::UntilBlocked);
+//! for addr in 0..50 {
+//!     let node = net.add(IntComp::new(mem.clone(), &[addr]), Framing::Packets(3));
+//!     net.nodes[node].idle_input = Some(-1);
+//! }
+//! net.run(&mut nat)?;
+//! ```
+
+use crate::{IntComp, IntcodeError, State};
+use std::fmt;
+use std::io::{self, Write};
+
+/// How a node's output is split up and routed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Framing {
+    /// Each value goes straight to the given node's input.
+    Stream(usize),
+    /// Values are grouped into packets of this many words. The first word
+    /// is the destination node and the rest is delivered there.
+    Packets(usize),
+}
+
+/// How long each node runs before the next one gets a turn.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Schedule {
+    /// Until it outputs a value, waits for input or halts.
+    RoundRobin,
+    /// Until it waits for input it doesn't have, or halts.
+    UntilBlocked,
+}
+
+/// What a hook wants the network to do next.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Control {
+    Continue,
+    Stop,
+}
+
+/// Why `Network::run` returned.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// A hook asked to stop.
+    Stopped,
+    /// Every node halted.
+    Halted,
+    /// Every live node is blocked on input nothing will ever send.
+    Deadlocked,
+}
+
+/// Why `Network::run` couldn't carry on.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NetworkError {
+    /// Node `node` streams its output to `to`, which isn't a node.
+    NoSuchNode {
+        node: usize,
+        to: usize,
+    },
+    /// Node `node` frames its output in packets of no words.
+    EmptyPackets {
+        node: usize,
+    },
+    Intcode(IntcodeError),
+}
+
+impl fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NetworkError::NoSuchNode { node, to } => {
+                write!(
+                    f,
+                    "node {} streams to node {}, which doesn't exist",
+                    node, to
+                )
+            }
+            NetworkError::EmptyPackets { node } => {
+                write!(f, "node {} sends packets of no words", node)
+            }
+            NetworkError::Intcode(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for NetworkError {}
+
+impl From<IntcodeError> for NetworkError {
+    fn from(e: IntcodeError) -> NetworkError {
+        NetworkError::Intcode(e)
+    }
+}
+
+/// Receives events from `Network::run`. Hooks given the network may send
+/// it packets, which is how devices outside it (like day 23's NAT) reply.
+pub trait Hook {
+    /// A packet or stream value reached node `to`.
+    fn deliver(&mut self, _from: usize, _to: usize, _data: &[isize]) -> Control {
+        Control::Continue
+    }
+    /// A packet was addressed to something that isn't a node.
+    fn unrouted(
+        &mut self,
+        _net: &mut Network,
+        _from: usize,
+        _dest: isize,
+        _data: &[isize],
+    ) -> Control {
+        Control::Continue
+    }
+    fn halted(&mut self, _node: usize) -> Control {
+        Control::Continue
+    }
+    /// Called after each round in which nothing was sent or received and
+    /// every live node ended blocked on input. `rounds` counts how many
+    /// such rounds there have been in a row.
+    fn idle(&mut self, _net: &mut Network, _rounds: usize) -> Control {
+        Control::Continue
+    }
+}
+
+impl Hook for () {}
+
+pub struct Node {
+    pub comp: IntComp,
+    pub framing: Framing,
+    /// Given to the machine when it asks for input and none is queued,
+    /// once per turn. With `None` the machine just waits.
+    pub idle_input: Option<isize>,
+    pub state: State,
+    partial: Vec<isize>,
+}
+
+pub struct Network {
+    pub nodes: Vec<Node>,
+    pub schedule: Schedule,
+    idle_rounds: usize,
+}
+
+impl Network {
+    pub fn new(schedule: Schedule) -> Self {
+        Network {
+            nodes: Vec::new(),
+            schedule,
+            idle_rounds: 0,
+        }
+    }
+
+    /// Adds a machine, returning its address.
+    pub fn add(&mut self, comp: IntComp, framing: Framing) -> usize {
+        self.nodes.push(Node {
+            comp,
+            framing,
+            idle_input: None,
+            state: State::Run,
+            partial: Vec::new(),
+        });
+        self.nodes.len() - 1
+    }
+
+    /// Queues `data` as input to node `to`.
+    pub fn send(&mut self, to: usize, data: &[isize]) {
+        self.nodes[to].comp.input.extend(data);
+    }
+
+    /// How many rounds in a row the network has been idle.
+    pub fn idle_rounds(&self) -> usize {
+        self.idle_rounds
+    }
+
+    /// Checks every node's output goes somewhere.
+    pub fn check(&self) -> Result<(), NetworkError> {
+        for (node, n) in self.nodes.iter().enumerate() {
+            match n.framing {
+                Framing::Stream(to) if to >= self.nodes.len() => {
+                    return Err(NetworkError::NoSuchNode { node, to })
+                }
+                Framing::Packets(0) => return Err(NetworkError::EmptyPackets { node }),
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+
+    /// Gives every live node one turn. Returns whether the round was idle,
+    /// or `None` if a hook asked to stop.
+    fn round(&mut self, hook: &mut impl Hook) -> Result<Option<bool>, IntcodeError> {
+        let mut idle = true;
+        for i in 0..self.nodes.len() {
+            if self.nodes[i].state == State::Halt {
+                continue;
+            }
+            let node = &mut self.nodes[i];
+            let mut fed_idle = false;
+            if !node.comp.input.is_empty() {
+                idle = false;
+            }
+            loop {
+                node.state = node.comp.run_until_io()?;
+                match node.state {
+                    State::Run => {
+                        idle = false;
+                        if self.schedule == Schedule::RoundRobin {
+                            break;
+                        }
+                    }
+                    State::NeedsInput => match node.idle_input {
+                        Some(val) if !fed_idle && node.comp.input.is_empty() => {
+                            node.comp.input.push_back(val);
+                            fed_idle = true;
+                        }
+                        _ => break,
+                    },
+                    State::Halt => {
+                        idle = false;
+                        break;
+                    }
+                }
+            }
+            if self.route(i, hook) == Control::Stop {
+                return Ok(None);
+            }
+            if self.nodes[i].state == State::Halt && hook.halted(i) == Control::Stop {
+                return Ok(None);
+            }
+        }
+        Ok(Some(idle))
+    }
+
+    /// Delivers what node `from` has output. Everything is routed even if
+    /// a hook asks to stop part way, so nothing is lost.
+    fn route(&mut self, from: usize, hook: &mut impl Hook) -> Control {
+        let node = &mut self.nodes[from];
+        let output: Vec<_> = node.comp.output.drain(..).collect();
+        let mut control = Control::Continue;
+        match node.framing {
+            Framing::Stream(to) => {
+                for val in output {
+                    self.nodes[to].comp.input.push_back(val);
+                    if hook.deliver(from, to, &[val]) == Control::Stop {
+                        control = Control::Stop;
+                    }
+                }
+            }
+            Framing::Packets(size) => {
+                node.partial.extend(output);
+                let words = node.partial.len() / size * size;
+                let packets: Vec<_> = node.partial.drain(..words).collect();
+                for packet in packets.chunks(size) {
+                    let (dest, data) = (packet[0], &packet[1..]);
+                    let next = if dest >= 0 && (dest as usize) < self.nodes.len() {
+                        self.send(dest as usize, data);
+                        hook.deliver(from, dest as usize, data)
+                    } else {
+                        hook.unrouted(self, from, dest, data)
+                    };
+                    if next == Control::Stop {
+                        control = Control::Stop;
+                    }
+                }
+            }
+        }
+        control
+    }
+
+    /// Runs rounds until a hook asks to stop, every node halts, or the
+    /// network can't make progress on its own. The wiring is checked
+    /// before each round, since hooks may change it.
+    pub fn run(&mut self, hook: &mut impl Hook) -> Result<Outcome, NetworkError> {
+        loop {
+            self.check()?;
+            let idle = match self.round(hook)? {
+                Some(idle) => idle,
+                None => return Ok(Outcome::Stopped),
+            };
+            if self.nodes.iter().all(|node| node.state == State::Halt) {
+                return Ok(Outcome::Halted);
+            }
+            if !idle {
+                self.idle_rounds = 0;
+                continue;
+            }
+            self.idle_rounds += 1;
+            if hook.idle(self, self.idle_rounds) == Control::Stop {
+                return Ok(Outcome::Stopped);
+            }
+            let stuck = self.nodes.iter().all(|node| {
+                node.state == State::Halt || node.idle_input.is_none() && node.comp.input.is_empty()
+            });
+            if stuck {
+                return Ok(Outcome::Deadlocked);
+            }
+        }
+    }
+}
+
+/// A packet as a `Nat` saw it. Addresses are signed since packets can be
+/// addressed to anything.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Packet {
+    pub from: isize,
+    pub to: isize,
+    pub data: Vec<isize>,
+}
+
+/// Day 23's NAT. It keeps the last `(x, y)` packet sent to `addr`, and
+/// once the network has been idle for `idle_rounds` rounds in a row sends
+/// it to node 0. It stops the network when it sends the same Y twice in a
+/// row, or when the network is idle and it has nothing to send.
+pub struct Nat {
+    pub addr: isize,
+    pub idle_rounds: usize,
+    /// The first packet it received.
+    pub first: Option<(isize, isize)>,
+    /// The first Y it sent twice in a row.
+    pub repeated_y: Option<isize>,
+    /// Every packet on the network, including the NAT's own.
+    pub log: Vec<Packet>,
+    last: Option<(isize, isize)>,
+    sent_y: Option<isize>,
+}
+
+impl Nat {
+    pub fn new(addr: isize, idle_rounds: usize) -> Self {
+        Nat {
+            addr,
+            idle_rounds,
+            first: None,
+            repeated_y: None,
+            log: Vec::new(),
+            last: None,
+            sent_y: None,
+        }
+    }
+
+    /// Writes the log one packet per line, as `from -> to: data`.
+    pub fn write_log(&self, mut out: impl Write) -> io::Result<()> {
+        for packet in &self.log {
+            let data: Vec<_> = packet.data.iter().map(|val| val.to_string()).collect();
+            writeln!(out, "{} -> {}: {}", packet.from, packet.to, data.join(","))?;
+        }
+        out.flush()
+    }
+
+    fn record(&mut self, from: isize, to: isize, data: &[isize]) {
+        self.log.push(Packet {
+            from,
+            to,
+            data: data.to_vec(),
+        });
+    }
+}
+
+impl Hook for Nat {
+    fn deliver(&mut self, from: usize, to: usize, data: &[isize]) -> Control {
+        self.record(from as isize, to as isize, data);
+        Control::Continue
+    }
+
+    fn unrouted(
+        &mut self,
+        _net: &mut Network,
+        from: usize,
+        dest: isize,
+        data: &[isize],
+    ) -> Control {
+        self.record(from as isize, dest, data);
+        if dest == self.addr && data.len() == 2 {
+            self.last = Some((data[0], data[1]));
+            self.first = self.first.or(self.last);
+        }
+        Control::Continue
+    }
+
+    fn idle(&mut self, net: &mut Network, rounds: usize) -> Control {
+        if rounds < self.idle_rounds {
+            return Control::Continue;
+        }
+        let (x, y) = match self.last {
+            Some(pkt) => pkt,
+            None => return Control::Stop,
+        };
+        self.record(self.addr, 0, &[x, y]);
+        net.send(0, &[x, y]);
+        if self.sent_y == Some(y) {
+            self.repeated_y = Some(y);
+            return Control::Stop;
+        }
+        self.sent_y = Some(y);
+        Control::Continue
+    }
+}
+
+#[test]
+fn test_network() {
+    // a ring of doublers that halts once a value passes 100.
+    let doubler = crate::asm::assemble(
+        "loop:  in -> [x]
+                mul [x], #2 -> [x]
+                out [x]
+                lt [x], #100 -> [t]
+                jt [t], #loop
+                hlt
+         x:     data 0
+         t:     data 0",
+    )
+    .unwrap();
+    let mut net = Network::new(Schedule::RoundRobin);
+    for i in 0..3 {
+        net.add(
+            IntComp::new(doubler.clone(), &[]),
+            Framing::Stream((i + 1) % 3),
+        );
+    }
+    net.send(0, &[1]);
+    struct Last(Option<isize>);
+    impl Hook for Last {
+        fn deliver(&mut self, from: usize, _to: usize, data: &[isize]) -> Control {
+            if from == 2 {
+                self.0 = Some(data[0]);
+            }
+            Control::Continue
+        }
+    }
+    let mut last = Last(None);
+    assert_eq!(net.run(&mut last), Ok(Outcome::Halted));
+    assert_eq!(last.0, Some(512));
+    assert_eq!(net.nodes[0].state, State::Halt);
+
+    // nodes send 9 to whatever address they're given, polling with -1.
+    let router = crate::asm::assemble(
+        "loop:  in -> [v]
+                eq [v], #-1 -> [t]
+                jt [t], #loop
+                out [v]
+                out #9
+                jt #1, #loop
+         v:     data 0
+         t:     data 0",
+    )
+    .unwrap();
+    let mut net = Network::new(Schedule::UntilBlocked);
+    for _ in 0..2 {
+        let node = net.add(IntComp::new(router.clone(), &[]), Framing::Packets(2));
+        net.nodes[node].idle_input = Some(-1);
+    }
+    net.send(0, &[1]);
+    #[derive(Default)]
+    struct Log(Vec<(usize, isize)>, Vec<usize>);
+    impl Hook for Log {
+        fn deliver(&mut self, from: usize, to: usize, _data: &[isize]) -> Control {
+            self.0.push((from, to as isize));
+            Control::Continue
+        }
+        fn unrouted(
+            &mut self,
+            _net: &mut Network,
+            from: usize,
+            dest: isize,
+            _data: &[isize],
+        ) -> Control {
+            self.0.push((from, dest));
+            Control::Continue
+        }
+        fn idle(&mut self, net: &mut Network, rounds: usize) -> Control {
+            self.1.push(rounds);
+            if self.1.len() == 1 {
+                net.send(1, &[9]);
+                return Control::Continue;
+            }
+            Control::Stop
+        }
+    }
+    let mut log = Log::default();
+    assert_eq!(net.run(&mut log), Ok(Outcome::Stopped));
+    assert_eq!(log.0, vec![(0, 1), (1, 9), (1, 9)]);
+    assert_eq!(log.1, vec![1, 1]);
+
+    // streams to a node that isn't there, and packets of nothing, are
+    // caught before anything runs.
+    let mut net = Network::new(Schedule::RoundRobin);
+    net.add(IntComp::new(doubler.clone(), &[1]), Framing::Stream(1));
+    assert_eq!(
+        net.run(&mut ()),
+        Err(NetworkError::NoSuchNode { node: 0, to: 1 })
+    );
+    assert!(net.nodes[0].comp.output.is_empty());
+    net.add(IntComp::new(router, &[]), Framing::Packets(0));
+    assert_eq!(
+        net.run(&mut ()),
+        Err(NetworkError::EmptyPackets { node: 1 })
+    );
+    net.nodes[1].framing = Framing::Stream(0);
+    assert_eq!(net.run(&mut ()), Ok(Outcome::Deadlocked));
+}
+
+#[test]
+fn test_nat() {
+    // reports to the NAT once, then ignores whatever it's sent.
+    let nic = crate::asm::assemble(
+        "       out #255
+                out #1
+                out #2
+         loop:  in -> [v]
+                jt #1, #loop
+         v:     data 0",
+    )
+    .unwrap();
+    let mut net = Network::new(Schedule::UntilBlocked);
+    let node = net.add(IntComp::new(nic, &[]), Framing::Packets(3));
+    net.nodes[node].idle_input = Some(-1);
+    let mut nat = Nat::new(255, 2);
+    assert_eq!(net.run(&mut nat), Ok(Outcome::Stopped));
+    assert_eq!(nat.first, Some((1, 2)));
+    assert_eq!(nat.repeated_y, Some(2));
+    let mut log = Vec::new();
+    nat.write_log(&mut log).unwrap();
+    assert_eq!(log, b"0 -> 255: 1,2\n255 -> 0: 1,2\n255 -> 0: 1,2\n");
+}
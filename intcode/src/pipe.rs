@@ -0,0 +1,245 @@
+//! Pluggable input and output, so machines can be wired to queues,
+//! channels, closures, files or each other.
+//!
+//! ```text
+//! let (tx, rx) = mpsc::channel();
+//! let first = pipe::spawn(IntComp::new(mem.clone(), &[]), input_rx, tx);
+//! let second = pipe::spawn(IntComp::new(mem, &[]), rx, output_tx);
+//! ```
+
+use crate::{IntComp, IntcodeError, State};
+use std::collections::VecDeque;
+use std::io::{self, BufRead, Write};
+use std::sync::mpsc::{Receiver, Sender};
+use std::thread::{self, JoinHandle};
+
+/// Where a machine's input comes from.
+pub trait Source {
+    /// The next value, or `None` if there isn't one. A source may block
+    /// until a value arrives.
+    fn recv(&mut self) -> Option<isize>;
+}
+
+/// Where a machine's output goes.
+pub trait Sink {
+    fn send(&mut self, val: isize);
+}
+
+impl<S: Source + ?Sized> Source for &mut S {
+    fn recv(&mut self) -> Option<isize> {
+        (**self).recv()
+    }
+}
+
+impl<S: Sink + ?Sized> Sink for &mut S {
+    fn send(&mut self, val: isize) {
+        (**self).send(val)
+    }
+}
+
+impl Source for VecDeque<isize> {
+    fn recv(&mut self) -> Option<isize> {
+        self.pop_front()
+    }
+}
+
+impl Sink for VecDeque<isize> {
+    fn send(&mut self, val: isize) {
+        self.push_back(val)
+    }
+}
+
+impl Sink for Vec<isize> {
+    fn send(&mut self, val: isize) {
+        self.push(val)
+    }
+}
+
+/// Blocks for the next value, giving `None` once every sender is gone.
+impl Source for Receiver<isize> {
+    fn recv(&mut self) -> Option<isize> {
+        Receiver::recv(self).ok()
+    }
+}
+
+/// Values sent after the receiver is gone are dropped.
+impl Sink for Sender<isize> {
+    fn send(&mut self, val: isize) {
+        let _ = Sender::send(self, val);
+    }
+}
+
+/// Adapts a closure: `FnMut() -> Option<isize>` as a source, or
+/// `FnMut(isize)` as a sink.
+pub struct Func<F>(pub F);
+
+impl<F: FnMut() -> Option<isize>> Source for Func<F> {
+    fn recv(&mut self) -> Option<isize> {
+        (self.0)()
+    }
+}
+
+impl<F: FnMut(isize)> Sink for Func<F> {
+    fn send(&mut self, val: isize) {
+        (self.0)(val)
+    }
+}
+
+/// Reads integers separated by commas or whitespace, ending at end of
+/// input or at the first token that isn't a number.
+pub struct Reader<R> {
+    inner: R,
+    pending: VecDeque<isize>,
+    done: bool,
+}
+
+impl<R: BufRead> Reader<R> {
+    pub fn new(inner: R) -> Self {
+        Reader {
+            inner,
+            pending: VecDeque::new(),
+            done: false,
+        }
+    }
+}
+
+impl<R: BufRead> Source for Reader<R> {
+    fn recv(&mut self) -> Option<isize> {
+        while self.pending.is_empty() && !self.done {
+            let mut line = String::new();
+            match self.inner.read_line(&mut line) {
+                Ok(0) | Err(_) => self.done = true,
+                Ok(_) => {
+                    for token in line.split(|c: char| c == ',' || c.is_whitespace()) {
+                        match token.parse() {
+                            Ok(val) => self.pending.push_back(val),
+                            Err(_) if token.is_empty() => (),
+                            Err(_) => {
+                                self.done = true;
+                                break;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        self.pending.pop_front()
+    }
+}
+
+/// Writes each value on its own line. The first write error stops output
+/// and is reported by `finish`.
+pub struct Writer<W: Write> {
+    inner: W,
+    error: Option<io::Error>,
+}
+
+impl<W: Write> Writer<W> {
+    pub fn new(inner: W) -> Self {
+        Writer { inner, error: None }
+    }
+
+    pub fn finish(mut self) -> io::Result<W> {
+        match self.error.take() {
+            Some(e) => Err(e),
+            None => self.inner.flush().map(|_| self.inner),
+        }
+    }
+}
+
+impl<W: Write> Sink for Writer<W> {
+    fn send(&mut self, val: isize) {
+        if self.error.is_none() {
+            if let Err(e) = writeln!(self.inner, "{}", val) {
+                self.error = Some(e);
+            }
+        }
+    }
+}
+
+impl IntComp {
+    /// Runs until the program halts, or waits for input that `input` can't
+    /// supply, sending each output value to `output` as it's produced.
+    /// Anything already queued in `self.input` is read first.
+    pub fn run_with(
+        &mut self,
+        mut input: impl Source,
+        mut output: impl Sink,
+    ) -> Result<State, IntcodeError> {
+        loop {
+            let state = self.run_until_io()?;
+            for val in self.output.drain(..) {
+                output.send(val);
+            }
+            match state {
+                State::Run => (),
+                State::NeedsInput => match input.recv() {
+                    Some(val) => self.input.push_back(val),
+                    None => return Ok(State::NeedsInput),
+                },
+                State::Halt => return Ok(State::Halt),
+            }
+        }
+    }
+}
+
+/// Runs `comp` with `run_with` on a new thread, handing the machine back
+/// when it halts or its input runs dry. Dropping the endpoints when the
+/// thread ends lets machines downstream finish too.
+pub fn spawn<I, O>(
+    mut comp: IntComp,
+    input: I,
+    output: O,
+) -> JoinHandle<Result<IntComp, IntcodeError>>
+where
+    I: Source + Send + 'static,
+    O: Sink + Send + 'static,
+{
+    thread::spawn(move || comp.run_with(input, output).map(|_| comp))
+}
+
+#[test]
+fn test_pipeline() {
+    use std::sync::mpsc::channel;
+    // doubles each input until its input closes.
+    let mem = crate::asm::assemble(
+        "loop:  in -> [x]
+                mul [x], #2 -> [x]
+                out [x]
+                jt #1, #loop
+         x:     data 0",
+    )
+    .unwrap();
+    let (input, mut rx) = channel();
+    let mut handles = Vec::new();
+    for _ in 0..3 {
+        let (next_tx, next_rx) = channel();
+        handles.push(spawn(IntComp::new(mem.clone(), &[]), rx, next_tx));
+        rx = next_rx;
+    }
+    for val in 1..=3 {
+        input.send(val).unwrap();
+    }
+    drop(input);
+    assert_eq!(rx.iter().collect::<Vec<_>>(), vec![8, 16, 24]);
+    for handle in handles {
+        assert_eq!(handle.join().unwrap().unwrap().pc, 0);
+    }
+
+    let mut comp = IntComp::new(mem, &[]);
+    let mut out = Writer::new(Vec::new());
+    let state = comp.run_with(Reader::new(&b"5, 6\n7 x 8"[..]), &mut out);
+    assert_eq!(state, Ok(State::NeedsInput));
+    assert_eq!(out.finish().unwrap(), b"10\n12\n14\n");
+    let mut seen = Vec::new();
+    let mut next = 0;
+    comp.run_with(
+        Func(|| {
+            next += 1;
+            Some(next).filter(|n| *n <= 2)
+        }),
+        Func(|val| seen.push(val)),
+    )
+    .unwrap();
+    assert_eq!(seen, vec![2, 4]);
+}
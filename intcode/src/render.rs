@@ -0,0 +1,218 @@
+//! Drawing the tile maps robots and games build up: a sparse grid of tile
+//! kinds, a palette saying how each looks, and either a plain text frame at
+//! the end or live updates on a terminal as tiles change.
+//!
+//! ```text
+//! let mut screen = Screen::new(vec![(Tile::Wall, '█'), (Tile::Ball, '●')]);
+//! screen.go_live(Box::new(io::stdout()), (0, 0))?;
+//! screen.set((x, y), Tile::Ball);
+//! screen.finish()?;
+//! println!("{}", screen.render());
+//! ```
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::io::{self, Write};
+
+pub type Pos = (isize, isize);
+
+/// Where live drawing goes. Grid position `origin` is drawn just below the
+/// status line, in the top left corner.
+struct Live {
+    out: Box<dyn Write>,
+    origin: Pos,
+    bottom: isize,
+    error: Option<io::Error>,
+}
+
+pub struct Screen<T> {
+    tiles: HashMap<Pos, T>,
+    palette: HashMap<T, char>,
+    /// Drawn for positions with no tile, or a tile not in the palette.
+    pub blank: char,
+    status: String,
+    live: Option<Live>,
+}
+
+impl<T: Copy + Eq + Hash> Screen<T> {
+    pub fn new(palette: impl IntoIterator<Item = (T, char)>) -> Self {
+        Screen {
+            tiles: HashMap::new(),
+            palette: palette.into_iter().collect(),
+            blank: ' ',
+            status: String::new(),
+            live: None,
+        }
+    }
+
+    pub fn get(&self, pos: Pos) -> Option<T> {
+        self.tiles.get(&pos).copied()
+    }
+
+    pub fn tiles(&self) -> &HashMap<Pos, T> {
+        &self.tiles
+    }
+
+    fn look(&self, pos: Pos) -> char {
+        self.get(pos)
+            .and_then(|tile| self.palette.get(&tile).copied())
+            .unwrap_or(self.blank)
+    }
+
+    pub fn set(&mut self, pos: Pos, tile: T) {
+        if self.tiles.insert(pos, tile) != Some(tile) {
+            self.draw(pos);
+        }
+    }
+
+    pub fn remove(&mut self, pos: Pos) -> Option<T> {
+        let tile = self.tiles.remove(&pos);
+        if tile.is_some() {
+            self.draw(pos);
+        }
+        tile
+    }
+
+    /// A line shown above the grid when live, like a score.
+    pub fn set_status(&mut self, status: impl Into<String>) {
+        self.status = status.into();
+        if let Some(live) = &mut self.live {
+            let res = write!(live.out, "\x1b[1;1H\x1b[2K{}", self.status);
+            live.keep(res);
+        }
+    }
+
+    /// The smallest and largest corners of the tiles set, if there are any.
+    pub fn bounds(&self) -> Option<(Pos, Pos)> {
+        let mut positions = self.tiles.keys();
+        let &first = positions.next()?;
+        Some(positions.fold((first, first), |(min, max), &(x, y)| {
+            ((min.0.min(x), min.1.min(y)), (max.0.max(x), max.1.max(y)))
+        }))
+    }
+
+    /// The whole grid as text, one line per row, each ending in a newline.
+    /// The status line isn't included.
+    pub fn render(&self) -> String {
+        let mut text = String::new();
+        if let Some((min, max)) = self.bounds() {
+            for y in min.1..=max.1 {
+                text.extend((min.0..=max.0).map(|x| self.look((x, y))));
+                text.push('\n');
+            }
+        }
+        text
+    }
+
+    /// Starts drawing to the terminal `out`: clears it, draws what's there so
+    /// far, and from now on redraws each tile as it changes. Tiles above or
+    /// left of `origin` aren't shown.
+    pub fn go_live(&mut self, out: Box<dyn Write>, origin: Pos) -> io::Result<()> {
+        let mut live = Live {
+            out,
+            origin,
+            bottom: origin.1,
+            error: None,
+        };
+        write!(live.out, "\x1b[2J\x1b[?25l\x1b[1;1H{}", self.status)?;
+        for (&pos, _) in self.tiles.iter() {
+            live.draw(pos, self.look(pos));
+        }
+        live.finish_write()?;
+        self.live = Some(live);
+        Ok(())
+    }
+
+    fn draw(&mut self, pos: Pos) {
+        let c = self.look(pos);
+        if let Some(live) = &mut self.live {
+            live.draw(pos, c);
+        }
+    }
+
+    /// Pushes live drawing out to the terminal, reporting the first error
+    /// since the last flush.
+    pub fn flush(&mut self) -> io::Result<()> {
+        match &mut self.live {
+            Some(live) => live.finish_write(),
+            None => Ok(()),
+        }
+    }
+
+    /// Stops live drawing, leaving the cursor below the grid.
+    pub fn finish(&mut self) -> io::Result<()> {
+        match self.live.take() {
+            Some(mut live) => {
+                let row = live.bottom - live.origin.1 + 3;
+                let res = write!(live.out, "\x1b[{};1H\x1b[?25h", row);
+                live.keep(res);
+                live.finish_write()
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+impl Live {
+    /// Holds on to the first error so drawing calls needn't return one.
+    fn keep(&mut self, res: io::Result<()>) {
+        if let Err(e) = res {
+            self.error.get_or_insert(e);
+        }
+    }
+
+    fn draw(&mut self, pos: Pos, c: char) {
+        let (col, row) = (pos.0 - self.origin.0, pos.1 - self.origin.1);
+        if col < 0 || row < 0 {
+            return;
+        }
+        self.bottom = self.bottom.max(pos.1);
+        // rows and columns count from 1, and the status line takes row 1.
+        let res = write!(self.out, "\x1b[{};{}H{}", row + 2, col + 1, c);
+        self.keep(res);
+    }
+
+    fn finish_write(&mut self) -> io::Result<()> {
+        match self.error.take() {
+            Some(e) => Err(e),
+            None => self.out.flush(),
+        }
+    }
+}
+
+#[test]
+fn test_screen() {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[derive(Clone)]
+    struct Shared(Rc<RefCell<Vec<u8>>>);
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let mut screen = Screen::new(vec![(1, '#'), (2, 'o')]);
+    assert_eq!(screen.render(), "");
+    screen.set((-1, 0), 1);
+    screen.set((1, 1), 2);
+    screen.set((0, 1), 3);
+    assert_eq!(screen.bounds(), Some(((-1, 0), (1, 1))));
+    assert_eq!(screen.render(), "#  \n  o\n");
+
+    let out = Shared(Rc::new(RefCell::new(Vec::new())));
+    screen.go_live(Box::new(out.clone()), (0, 0)).unwrap();
+    out.0.borrow_mut().clear();
+    screen.set_status("SCORE: 1");
+    screen.set((1, 1), 2);
+    screen.remove((1, 1));
+    screen.finish().unwrap();
+    assert_eq!(
+        String::from_utf8(out.0.borrow().clone()).unwrap(),
+        "\x1b[1;1H\x1b[2KSCORE: 1\x1b[3;2H \x1b[4;1H\x1b[?25h"
+    );
+}
@@ -0,0 +1,182 @@
+//! Saving and restoring a whole machine.
+//!
+//! The format is a magic header followed by LEB128 varints, with signed
+//! values zigzag-encoded so that the small numbers which make up most of an
+//! Intcode image take a byte or two each:
+//!
+//! ```text
+//! "ICSNAP1" pc rel_base limit sparse_from
+//! len dense[len]
+//! page_count (page_index page[PAGE_SIZE])*
+//! len input[len]
+//! len output[len]
+//! ```
+
+use crate::mem::PAGE_SIZE;
+use crate::{IntComp, Memory};
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+const MAGIC: &[u8] = b"ICSNAP1";
+
+fn write_u64(w: &mut impl Write, mut val: u64) -> io::Result<()> {
+    loop {
+        let byte = (val & 0x7f) as u8;
+        val >>= 7;
+        if val == 0 {
+            return w.write_all(&[byte]);
+        }
+        w.write_all(&[byte | 0x80])?;
+    }
+}
+
+fn write_i64(w: &mut impl Write, val: i64) -> io::Result<()> {
+    write_u64(w, ((val << 1) ^ (val >> 63)) as u64)
+}
+
+fn write_words<'a>(
+    w: &mut impl Write,
+    words: impl ExactSizeIterator<Item = &'a isize>,
+) -> io::Result<()> {
+    write_u64(w, words.len() as u64)?;
+    for word in words {
+        write_i64(w, *word as i64)?;
+    }
+    Ok(())
+}
+
+fn read_u64(r: &mut impl Read) -> io::Result<u64> {
+    let mut val = 0u64;
+    for shift in (0..64).step_by(7) {
+        let mut byte = [0];
+        r.read_exact(&mut byte)?;
+        val |= ((byte[0] & 0x7f) as u64) << shift;
+        if byte[0] & 0x80 == 0 {
+            return Ok(val);
+        }
+    }
+    Err(invalid("varint too long"))
+}
+
+fn read_i64(r: &mut impl Read) -> io::Result<i64> {
+    let val = read_u64(r)?;
+    Ok((val >> 1) as i64 ^ -((val & 1) as i64))
+}
+
+fn read_usize(r: &mut impl Read) -> io::Result<usize> {
+    let val = read_u64(r)?;
+    if val > usize::MAX as u64 {
+        return Err(invalid("value too large for this platform"));
+    }
+    Ok(val as usize)
+}
+
+fn read_words(r: &mut impl Read, limit: usize) -> io::Result<Vec<isize>> {
+    let len = read_usize(r)?;
+    if len > limit {
+        return Err(invalid("word count beyond the memory limit"));
+    }
+    (0..len).map(|_| Ok(read_i64(r)? as isize)).collect()
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+impl IntComp {
+    pub fn save(&self, mut w: impl Write) -> io::Result<()> {
+        let mem = &self.mem;
+        w.write_all(MAGIC)?;
+        write_u64(&mut w, self.pc as u64)?;
+        write_i64(&mut w, self.rel_base as i64)?;
+        write_u64(&mut w, mem.limit as u64)?;
+        write_u64(&mut w, mem.sparse_from as u64)?;
+        write_words(&mut w, mem.dense.iter())?;
+        let mut pages: Vec<_> = mem.pages.iter().collect();
+        pages.sort_by_key(|(index, _)| **index);
+        write_u64(&mut w, pages.len() as u64)?;
+        for (index, page) in pages {
+            write_u64(&mut w, *index as u64)?;
+            for word in page.iter() {
+                write_i64(&mut w, *word as i64)?;
+            }
+        }
+        write_words(&mut w, self.input.iter())?;
+        write_words(&mut w, self.output.iter())?;
+        w.flush()
+    }
+
+    pub fn restore(mut r: impl Read) -> io::Result<IntComp> {
+        let mut magic = [0; 7];
+        r.read_exact(&mut magic)?;
+        if magic != MAGIC {
+            return Err(invalid("not an Intcode snapshot"));
+        }
+        let pc = read_usize(&mut r)?;
+        let rel_base = read_i64(&mut r)? as isize;
+        let limit = read_usize(&mut r)?;
+        let sparse_from = read_usize(&mut r)?;
+        let dense = read_words(&mut r, limit)?;
+        let mut pages = HashMap::new();
+        for _ in 0..read_usize(&mut r)? {
+            let index = read_usize(&mut r)?;
+            let page = (0..PAGE_SIZE)
+                .map(|_| Ok(read_i64(&mut r)? as isize))
+                .collect::<io::Result<Vec<_>>>()?;
+            pages.insert(index, page.into_boxed_slice());
+        }
+        let input: VecDeque<_> = read_words(&mut r, usize::MAX)?.into();
+        let output: VecDeque<_> = read_words(&mut r, usize::MAX)?.into();
+        Ok(IntComp {
+            mem: Memory {
+                dense,
+                pages,
+                sparse_from,
+                limit,
+                ops: Vec::new(),
+            },
+            input,
+            output,
+            pc,
+            rel_base,
+        })
+    }
+
+    pub fn save_to(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.save(BufWriter::new(File::create(path)?))
+    }
+
+    pub fn restore_from(path: impl AsRef<Path>) -> io::Result<IntComp> {
+        IntComp::restore(BufReader::new(File::open(path)?))
+    }
+}
+
+#[test]
+fn test_snapshot_round_trip() {
+    use crate::State;
+    // echoes its input, with a word poked far out into sparse memory.
+    let mem = crate::asm::assemble(
+        "       arb #1000000
+         loop:  in -> [rb+0]
+                out [rb+0]
+                jt #1, #loop",
+    )
+    .unwrap();
+    let mut comp = IntComp::new(Memory::new(mem).sparse_above(100), &[-7, 1 << 40]);
+    comp.step().unwrap();
+    comp.step().unwrap();
+    let mut bytes = Vec::new();
+    comp.save(&mut bytes).unwrap();
+    let restored = IntComp::restore(&bytes[..]).unwrap();
+    assert_eq!(restored.pc, comp.pc);
+    assert_eq!(restored.rel_base, 1_000_000);
+    assert_eq!(restored.mem.get(1_000_000), -7);
+    for comp in &mut [comp, restored.clone()] {
+        while let State::Run = comp.step().unwrap() {}
+        assert_eq!(comp.output, vec![-7, 1 << 40]);
+    }
+    bytes[0] = b'X';
+    assert!(IntComp::restore(&bytes[..]).is_err());
+}
@@ -0,0 +1,695 @@
+//! Day 21's springscript: parsing and checking programs before they're sent
+//! to the droid, compiling boolean expressions over the sensors, and a
+//! local droid to try programs on.
+//!
+//! ```text
+//! let expr = Expr::parse("!(A & B & C) & D")?;
+//! let program = springscript::compile(&expr, Mode::Walk)?;
+//! assert_eq!(program.simulate("#####.#..########"), Ok(()));
+//! console.send(&program.to_string());
+//! ```
+
+use crate::ascii::Console;
+use crate::{IntComp, IntcodeError};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// How many instructions the droid will accept.
+pub const MAX_INSTRS: usize = 15;
+
+/// Sensors `A` to `I`, numbered from 0, see that many tiles plus one
+/// ahead. `T` and `J` are the writable registers; `J` decides the jump.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Reg {
+    Sensor(u8),
+    T,
+    J,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Op {
+    And,
+    Or,
+    Not,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Instr {
+    pub op: Op,
+    pub src: Reg,
+    pub dest: Reg,
+}
+
+/// `Walk` can only use sensors `A` to `D`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Mode {
+    Walk,
+    Run,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Program {
+    pub instrs: Vec<Instr>,
+    pub mode: Mode,
+}
+
+/// Lines are counted from 1.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    Syntax { line: usize, text: String },
+    MissingMode,
+    TooLong(usize),
+    NotWritable { line: usize, reg: Reg },
+    NeedsRun { line: usize, reg: Reg },
+    TooComplex,
+}
+
+impl fmt::Display for Reg {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Reg::Sensor(i) => write!(f, "{}", (b'A' + i) as char),
+            Reg::T => write!(f, "T"),
+            Reg::J => write!(f, "J"),
+        }
+    }
+}
+
+impl fmt::Display for Instr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let op = match self.op {
+            Op::And => "AND",
+            Op::Or => "OR",
+            Op::Not => "NOT",
+        };
+        write!(f, "{} {} {}", op, self.src, self.dest)
+    }
+}
+
+/// The program as the droid expects it, ending in `WALK` or `RUN` and a
+/// newline.
+impl fmt::Display for Program {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for instr in &self.instrs {
+            writeln!(f, "{}", instr)?;
+        }
+        match self.mode {
+            Mode::Walk => writeln!(f, "WALK"),
+            Mode::Run => writeln!(f, "RUN"),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Syntax { line, text } => write!(f, "line {}: can't parse {:?}", line, text),
+            Error::MissingMode => write!(f, "program doesn't end in WALK or RUN"),
+            Error::TooLong(len) => {
+                write!(f, "{} instructions, but at most {} fit", len, MAX_INSTRS)
+            }
+            Error::NotWritable { line, reg } => write!(f, "line {}: can't write to {}", line, reg),
+            Error::NeedsRun { line, reg } => {
+                write!(f, "line {}: sensor {} is only there in RUN mode", line, reg)
+            }
+            Error::TooComplex => write!(f, "expression needs more than two registers"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl Reg {
+    fn parse(name: &str) -> Option<Reg> {
+        match name.as_bytes() {
+            b"T" => Some(Reg::T),
+            b"J" => Some(Reg::J),
+            &[c] if (b'A'..=b'I').contains(&c) => Some(Reg::Sensor(c - b'A')),
+            _ => None,
+        }
+    }
+
+    fn get(self, sensors: [bool; 9], t: bool, j: bool) -> bool {
+        match self {
+            Reg::Sensor(i) => sensors[i as usize],
+            Reg::T => t,
+            Reg::J => j,
+        }
+    }
+}
+
+/// What the sensors see from `hull[pos]`. Tiles past the end are ground.
+fn sees(hull: &[u8], pos: usize) -> [bool; 9] {
+    let mut sensors = [true; 9];
+    for (i, sensor) in sensors.iter_mut().enumerate() {
+        *sensor = hull.get(pos + 1 + i) != Some(&b'.');
+    }
+    sensors
+}
+
+impl Program {
+    /// Parses a program, ignoring blank lines. It isn't `validate`d.
+    pub fn parse(src: &str) -> Result<Program, Error> {
+        let mut instrs = Vec::new();
+        for (i, text) in src.lines().enumerate() {
+            let words: Vec<_> = text.split_whitespace().collect();
+            let syntax = || Error::Syntax {
+                line: i + 1,
+                text: text.to_string(),
+            };
+            let op = match words.as_slice() {
+                [] => continue,
+                ["WALK"] | ["RUN"] => {
+                    let mode = if words[0] == "WALK" {
+                        Mode::Walk
+                    } else {
+                        Mode::Run
+                    };
+                    if src.lines().skip(i + 1).any(|line| !line.trim().is_empty()) {
+                        return Err(syntax());
+                    }
+                    return Ok(Program { instrs, mode });
+                }
+                ["AND", ..] => Op::And,
+                ["OR", ..] => Op::Or,
+                ["NOT", ..] => Op::Not,
+                _ => return Err(syntax()),
+            };
+            match words[1..] {
+                [src, dest] => instrs.push(Instr {
+                    op,
+                    src: Reg::parse(src).ok_or_else(syntax)?,
+                    dest: Reg::parse(dest).ok_or_else(syntax)?,
+                }),
+                _ => return Err(syntax()),
+            }
+        }
+        Err(Error::MissingMode)
+    }
+
+    /// Checks what the droid would reject: too many instructions, writes
+    /// to sensors, and sensors past `D` in `WALK` mode.
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.instrs.len() > MAX_INSTRS {
+            return Err(Error::TooLong(self.instrs.len()));
+        }
+        for (i, instr) in self.instrs.iter().enumerate() {
+            let line = i + 1;
+            if let Reg::Sensor(_) = instr.dest {
+                return Err(Error::NotWritable {
+                    line,
+                    reg: instr.dest,
+                });
+            }
+            match instr.src {
+                Reg::Sensor(s) if s > 3 && self.mode == Mode::Walk => {
+                    return Err(Error::NeedsRun {
+                        line,
+                        reg: instr.src,
+                    })
+                }
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether the droid jumps, given what sensors `A` to `I` see.
+    pub fn eval(&self, sensors: [bool; 9]) -> bool {
+        let (mut t, mut j) = (false, false);
+        for instr in &self.instrs {
+            let src = instr.src.get(sensors, t, j);
+            let dest = instr.dest.get(sensors, t, j);
+            let val = match instr.op {
+                Op::And => src && dest,
+                Op::Or => src || dest,
+                Op::Not => !src,
+            };
+            match instr.dest {
+                Reg::T => t = val,
+                Reg::J => j = val,
+                Reg::Sensor(_) => (),
+            }
+        }
+        j
+    }
+
+    /// Sends a droid along `hull`, a row of `#` (ground) and `.` (hole)
+    /// with the droid starting on the first tile. Gives the tile it falls
+    /// through, if it does.
+    pub fn simulate(&self, hull: &str) -> Result<(), usize> {
+        let hull = hull.as_bytes();
+        let mut pos = 0;
+        while pos < hull.len() {
+            if hull[pos] == b'.' {
+                return Err(pos);
+            }
+            pos += if self.eval(sees(hull, pos)) { 4 } else { 1 };
+        }
+        Ok(())
+    }
+}
+
+/// A boolean expression over the sensors.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Expr {
+    Sensor(u8),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expr::Sensor(i) => write!(f, "{}", Reg::Sensor(*i)),
+            Expr::Not(a) => write!(f, "!{}", a),
+            Expr::And(a, b) => write!(f, "({} & {})", a, b),
+            Expr::Or(a, b) => write!(f, "({} | {})", a, b),
+        }
+    }
+}
+
+impl Expr {
+    /// Parses sensors `A` to `I` combined with `!`, `&` and `|`, binding
+    /// in that order, and parentheses.
+    pub fn parse(src: &str) -> Result<Expr, Error> {
+        let tokens: Vec<char> = src.chars().filter(|c| !c.is_whitespace()).collect();
+        let mut pos = 0;
+        let expr = Expr::parse_or(&tokens, &mut pos);
+        match expr {
+            Some(expr) if pos == tokens.len() => Ok(expr),
+            _ => Err(Error::Syntax {
+                line: 1,
+                text: src.to_string(),
+            }),
+        }
+    }
+
+    fn parse_or(tokens: &[char], pos: &mut usize) -> Option<Expr> {
+        let mut expr = Expr::parse_and(tokens, pos)?;
+        while tokens.get(*pos) == Some(&'|') {
+            *pos += 1;
+            expr = Expr::Or(Box::new(expr), Box::new(Expr::parse_and(tokens, pos)?));
+        }
+        Some(expr)
+    }
+
+    fn parse_and(tokens: &[char], pos: &mut usize) -> Option<Expr> {
+        let mut expr = Expr::parse_not(tokens, pos)?;
+        while tokens.get(*pos) == Some(&'&') {
+            *pos += 1;
+            expr = Expr::And(Box::new(expr), Box::new(Expr::parse_not(tokens, pos)?));
+        }
+        Some(expr)
+    }
+
+    fn parse_not(tokens: &[char], pos: &mut usize) -> Option<Expr> {
+        let c = *tokens.get(*pos)?;
+        *pos += 1;
+        match c {
+            '!' => Some(Expr::Not(Box::new(Expr::parse_not(tokens, pos)?))),
+            '(' => {
+                let expr = Expr::parse_or(tokens, pos)?;
+                if tokens.get(*pos) != Some(&')') {
+                    return None;
+                }
+                *pos += 1;
+                Some(expr)
+            }
+            'A'..='I' => Some(Expr::Sensor(c as u8 - b'A')),
+            _ => None,
+        }
+    }
+
+    pub fn eval(&self, sensors: [bool; 9]) -> bool {
+        match self {
+            Expr::Sensor(i) => sensors[*i as usize],
+            Expr::Not(a) => !a.eval(sensors),
+            Expr::And(a, b) => a.eval(sensors) && b.eval(sensors),
+            Expr::Or(a, b) => a.eval(sensors) || b.eval(sensors),
+        }
+    }
+
+    /// The expression for `!self`, with the negation pushed one level in.
+    fn negate(&self) -> Expr {
+        match self {
+            Expr::Sensor(_) => Expr::Not(Box::new(self.clone())),
+            Expr::Not(a) => (**a).clone(),
+            Expr::And(a, b) => Expr::Or(Box::new(a.negate()), Box::new(b.negate())),
+            Expr::Or(a, b) => Expr::And(Box::new(a.negate()), Box::new(b.negate())),
+        }
+    }
+}
+
+fn instr(op: Op, src: Reg, dest: Reg) -> Instr {
+    Instr { op, src, dest }
+}
+
+fn other(reg: Reg) -> Reg {
+    if reg == Reg::T {
+        Reg::J
+    } else {
+        Reg::T
+    }
+}
+
+/// Registers start out false, which saves loading the first value.
+#[derive(Clone, Copy)]
+struct Fresh {
+    t: bool,
+    j: bool,
+}
+
+impl Fresh {
+    fn get(self, reg: Reg) -> bool {
+        match reg {
+            Reg::T => self.t,
+            _ => self.j,
+        }
+    }
+
+    fn after(self, code: &[Instr]) -> Fresh {
+        Fresh {
+            t: self.t && code.iter().all(|instr| instr.dest != Reg::T),
+            j: self.j && code.iter().all(|instr| instr.dest != Reg::J),
+        }
+    }
+}
+
+fn shortest(options: impl IntoIterator<Item = Option<Vec<Instr>>>) -> Option<Vec<Instr>> {
+    options.into_iter().flatten().min_by_key(|code| code.len())
+}
+
+/// Code leaving `expr` in `reg`, free to clobber the other register only if
+/// `spare`. `None` if that can't be done. Negations are only pushed through
+/// `expr` itself if `flip`, so rewrites can't undo each other forever.
+fn gen(expr: &Expr, reg: Reg, spare: bool, flip: bool, fresh: Fresh) -> Option<Vec<Instr>> {
+    match expr {
+        Expr::Sensor(s) => {
+            let s = Reg::Sensor(*s);
+            if fresh.get(reg) {
+                Some(vec![instr(Op::Or, s, reg)])
+            } else {
+                Some(vec![instr(Op::Not, s, reg), instr(Op::Not, reg, reg)])
+            }
+        }
+        Expr::Not(a) => match **a {
+            Expr::Sensor(s) => Some(vec![instr(Op::Not, Reg::Sensor(s), reg)]),
+            _ => shortest(vec![
+                gen(a, reg, spare, true, fresh).map(|mut code| {
+                    code.push(instr(Op::Not, reg, reg));
+                    code
+                }),
+                gen(&a.negate(), reg, spare, false, fresh),
+            ]),
+        },
+        Expr::And(a, b) | Expr::Or(a, b) => {
+            let op = if let Expr::And(..) = expr {
+                Op::And
+            } else {
+                Op::Or
+            };
+            let mut options = Vec::new();
+            for (a, b) in [(a, b), (b, a)] {
+                options.push(combine(op, a, b, reg, spare, fresh));
+            }
+            if flip {
+                options.push(
+                    gen(&expr.negate(), reg, spare, false, fresh).map(|mut code| {
+                        code.push(instr(Op::Not, reg, reg));
+                        code
+                    }),
+                );
+            }
+            shortest(options)
+        }
+    }
+}
+
+/// `a op b` in `reg`, with `a` computed there first.
+fn combine(op: Op, a: &Expr, b: &Expr, reg: Reg, spare: bool, fresh: Fresh) -> Option<Vec<Instr>> {
+    if let Expr::Sensor(s) = *b {
+        let mut code = gen(a, reg, spare, true, fresh)?;
+        code.push(instr(op, Reg::Sensor(s), reg));
+        return Some(code);
+    }
+    if !spare {
+        return None;
+    }
+    let scratch = other(reg);
+    // either b goes in the scratch register after a, using only that...
+    let after = gen(a, reg, true, true, fresh).and_then(|mut code| {
+        code.extend(gen(b, scratch, false, true, fresh.after(&code))?);
+        code.push(instr(op, scratch, reg));
+        Some(code)
+    });
+    // ...or before it, leaving a to manage with one register.
+    let before = gen(b, scratch, true, true, fresh).and_then(|mut code| {
+        code.extend(gen(a, reg, false, true, fresh.after(&code))?);
+        code.push(instr(op, scratch, reg));
+        Some(code)
+    });
+    shortest(vec![after, before])
+}
+
+/// The shortest program found for `expr`, trying each way round of every
+/// `&` and `|` and pushing negations in or out. The result is validated.
+pub fn compile(expr: &Expr, mode: Mode) -> Result<Program, Error> {
+    let fresh = Fresh { t: true, j: true };
+    let instrs = gen(expr, Reg::J, true, true, fresh).ok_or(Error::TooComplex)?;
+    let program = Program { instrs, mode };
+    program.validate()?;
+    Ok(program)
+}
+
+/// A droid waiting for its program, so each attempt starts from a copy
+/// rather than rerunning the start-up code.
+pub struct Droid {
+    comp: IntComp,
+}
+
+/// How a droid got on.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Attempt {
+    /// It made it across, reporting this much hull damage.
+    Survived(isize),
+    /// It fell on this hull, as `simulate` takes it. Empty if the droid's
+    /// output couldn't be read.
+    Fell(String),
+}
+
+impl Droid {
+    pub fn new(mem: Vec<isize>) -> Result<Droid, IntcodeError> {
+        let mut console = Console::new(IntComp::new(mem, &[]));
+        console.run()?;
+        Ok(Droid { comp: console.comp })
+    }
+
+    pub fn attempt(&self, program: &Program) -> Result<Attempt, IntcodeError> {
+        let mut console = Console::new(self.comp.clone());
+        console.send(&program.to_string());
+        console.run()?;
+        if let Some(&damage) = console.values().last() {
+            return Ok(Attempt::Survived(damage));
+        }
+        // the first frame of the fall shows the droid on tile 0.
+        let text = console.take_text();
+        let hull = text
+            .lines()
+            .find(|line| line.contains('#') && line.bytes().all(|c| c == b'#' || c == b'.'));
+        Ok(Attempt::Fell(hull.unwrap_or_default().to_string()))
+    }
+}
+
+/// Expressions found so far, told apart only by whether the droid jumps
+/// from each view seen on the known hulls, since that's all that decides
+/// whether it gets across them.
+struct Search<'a> {
+    hulls: &'a [String],
+    walks: Vec<Vec<usize>>,
+    mode: Mode,
+    seen: HashSet<Vec<bool>>,
+}
+
+impl Search<'_> {
+    fn crosses(&self, jumps: &[bool]) -> bool {
+        self.hulls.iter().zip(&self.walks).all(|(hull, walk)| {
+            let mut pos = 0;
+            while pos < hull.len() {
+                if hull.as_bytes()[pos] == b'.' {
+                    return false;
+                }
+                pos += if jumps[walk[pos]] { 4 } else { 1 };
+            }
+            true
+        })
+    }
+
+    /// Adds `expr` to `level` if it's new and compiles, giving its program
+    /// if it gets across every hull. One that doesn't compile leaves the
+    /// way open for a later equivalent that does.
+    fn offer(
+        &mut self,
+        expr: Expr,
+        jumps: Vec<bool>,
+        level: &mut Vec<(Expr, Vec<bool>)>,
+    ) -> Option<Program> {
+        if self.seen.contains(&jumps) {
+            return None;
+        }
+        let crosses = self.crosses(&jumps);
+        let program = compile(&expr, self.mode).ok()?;
+        self.seen.insert(jumps.clone());
+        level.push((expr, jumps));
+        Some(program).filter(|_| crosses)
+    }
+}
+
+/// The first program, smallest expression first, that gets across every
+/// hull in `hulls`.
+fn search(hulls: &[String], mode: Mode, max_size: usize) -> Option<Program> {
+    let mut views = HashMap::new();
+    let walks = hulls
+        .iter()
+        .map(|hull| {
+            (0..hull.len())
+                .map(|pos| {
+                    let next = views.len();
+                    *views.entry(sees(hull.as_bytes(), pos)).or_insert(next)
+                })
+                .collect()
+        })
+        .collect();
+    let mut views: Vec<_> = views.into_iter().collect();
+    views.sort_by_key(|&(_, i)| i);
+    let mut search = Search {
+        hulls,
+        walks,
+        mode,
+        seen: HashSet::new(),
+    };
+    let sensors = if mode == Mode::Walk { 4 } else { 9 };
+    // levels[n] holds expressions with n sensor terms.
+    let mut levels: Vec<Vec<(Expr, Vec<bool>)>> = vec![Vec::new(); max_size + 1];
+    for size in 1..=max_size {
+        let mut level = Vec::new();
+        if size == 1 {
+            for s in 0..sensors {
+                for expr in [Expr::Sensor(s), Expr::Sensor(s).negate()] {
+                    let jumps = views.iter().map(|(view, _)| expr.eval(*view)).collect();
+                    if let Some(program) = search.offer(expr, jumps, &mut level) {
+                        return Some(program);
+                    }
+                }
+            }
+        }
+        for left in 1..=size / 2 {
+            let right = size - left;
+            for (i, (a, a_jumps)) in levels[left].iter().enumerate() {
+                let start = if left == right { i } else { 0 };
+                for (b, b_jumps) in &levels[right][start..] {
+                    let pairs = a_jumps.iter().zip(b_jumps);
+                    let and = pairs.clone().map(|(a, b)| *a && *b).collect();
+                    let or = pairs.map(|(a, b)| *a || *b).collect();
+                    let (a, b) = (Box::new(a.clone()), Box::new(b.clone()));
+                    let found = search
+                        .offer(Expr::And(a.clone(), b.clone()), and, &mut level)
+                        .or_else(|| search.offer(Expr::Or(a, b), or, &mut level));
+                    if found.is_some() {
+                        return found;
+                    }
+                }
+            }
+        }
+        levels[size] = level;
+    }
+    None
+}
+
+/// Looks for a program the droid gets across with. Candidates built from
+/// at most `max_size` sensor terms are checked, smallest first, against
+/// every hull the droid has fallen on so far; the first that clears them
+/// all is sent to the droid, and if that falls too its hull is learned and
+/// the search goes again. Gives the program and the damage it reports.
+pub fn synthesize(
+    droid: &Droid,
+    mode: Mode,
+    max_size: usize,
+) -> Result<Option<(Program, isize)>, IntcodeError> {
+    let mut hulls = Vec::new();
+    while let Some(program) = search(&hulls, mode, max_size) {
+        match droid.attempt(&program)? {
+            Attempt::Survived(damage) => return Ok(Some((program, damage))),
+            // a hull already known means the droid and `simulate` disagree.
+            Attempt::Fell(hull) if hull.is_empty() || hulls.contains(&hull) => break,
+            Attempt::Fell(hull) => hulls.push(hull),
+        }
+    }
+    Ok(None)
+}
+
+#[test]
+fn test_springscript() {
+    let src = "NOT A T\nOR T J\nNOT B T\nOR T J\nNOT C T\nOR T J\nAND D J\nWALK\n";
+    let program = Program::parse(src).unwrap();
+    assert_eq!(program.to_string(), src);
+    assert_eq!(program.validate(), Ok(()));
+    assert_eq!(program.simulate("#####.#..########"), Ok(()));
+    let never = Program::parse("WALK").unwrap();
+    assert_eq!(never.simulate("#####.#..########"), Err(5));
+
+    let walk_e = Program::parse("OR E J\nWALK").unwrap();
+    assert_eq!(
+        walk_e.validate(),
+        Err(Error::NeedsRun {
+            line: 1,
+            reg: Reg::Sensor(4)
+        })
+    );
+    assert!(Program::parse("OR T A\nWALK").unwrap().validate().is_err());
+    assert!(Program::parse("XOR A J\nWALK").is_err());
+    assert_eq!(Program::parse("OR A J"), Err(Error::MissingMode));
+
+    let expr = Expr::parse("!(A & B & C) & D").unwrap();
+    let compiled = compile(&expr, Mode::Walk).unwrap();
+    assert_eq!(
+        compiled.to_string(),
+        "OR A J\nAND B J\nAND C J\nNOT J J\nAND D J\nWALK\n"
+    );
+    for bits in 0..512 {
+        let sensors = [0, 1, 2, 3, 4, 5, 6, 7, 8].map(|i| bits & (1 << i) != 0);
+        assert_eq!(compiled.eval(sensors), expr.eval(sensors));
+        assert_eq!(program.eval(sensors), expr.eval(sensors));
+    }
+
+    let hulls = vec![
+        "#####.#..########".to_string(),
+        "#####...#########".to_string(),
+    ];
+    let found = search(&hulls, Mode::Walk, 4).unwrap();
+    assert!(hulls.iter().all(|hull| found.simulate(hull).is_ok()));
+    assert_eq!(search(&hulls, Mode::Walk, 0), None);
+
+    // with no hulls every expression looks the same, so the first that
+    // compiles is the one found.
+    let mut search = Search {
+        hulls: &[],
+        walks: vec![],
+        mode: Mode::Walk,
+        seen: HashSet::new(),
+    };
+    let mut level = Vec::new();
+    let too_big = Expr::parse("((A & B) | (C & D)) & ((A | B) & (C | D))").unwrap();
+    assert_eq!(compile(&too_big, Mode::Walk), Err(Error::TooComplex));
+    assert_eq!(search.offer(too_big, vec![], &mut level), None);
+    let found = search.offer(Expr::parse("D").unwrap(), vec![], &mut level);
+    assert_eq!(
+        found.map(|p| p.to_string()),
+        Some("OR D J\nWALK\n".to_string())
+    );
+    assert_eq!(level.len(), 1);
+    assert_eq!(
+        search.offer(Expr::parse("A").unwrap(), vec![], &mut level),
+        None
+    );
+}
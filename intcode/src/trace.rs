@@ -0,0 +1,262 @@
+//! Hooks for watching a program run: an instruction log and a profiler.
+
+use crate::{Arg, Instr, IntComp};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Receives events from `IntComp::step_with`. `instr` is called just before
+/// an instruction executes, so `comp` still holds its operands.
+pub trait Tracer {
+    fn instr(&mut self, _pc: usize, _instr: &Instr, _comp: &IntComp) {}
+    fn input(&mut self, _val: isize) {}
+    fn output(&mut self, _val: isize) {}
+}
+
+impl Tracer for () {}
+
+impl<A: Tracer, B: Tracer> Tracer for (A, B) {
+    fn instr(&mut self, pc: usize, instr: &Instr, comp: &IntComp) {
+        self.0.instr(pc, instr, comp);
+        self.1.instr(pc, instr, comp);
+    }
+    fn input(&mut self, val: isize) {
+        self.0.input(val);
+        self.1.input(val);
+    }
+    fn output(&mut self, val: isize) {
+        self.0.output(val);
+        self.1.output(val);
+    }
+}
+
+fn sources(instr: &Instr) -> Vec<&Arg> {
+    use Instr::*;
+    match instr {
+        Add(a, b, _) | Mul(a, b, _) | LessThan(a, b, _) | Equals(a, b, _) => vec![a, b],
+        JumpIf(a, b) | JumpUnless(a, b) => vec![a, b],
+        Output(a) | SetRelBase(a) => vec![a],
+        Input(_) | Halt => vec![],
+    }
+}
+
+/// Writes one line per executed instruction, with the values of its source
+/// operands, plus a line per input or output value.
+pub struct LogTracer<W: Write> {
+    out: W,
+}
+
+impl LogTracer<BufWriter<File>> {
+    pub fn to_file(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(LogTracer::new(BufWriter::new(File::create(path)?)))
+    }
+}
+
+impl<W: Write> LogTracer<W> {
+    pub fn new(out: W) -> Self {
+        LogTracer { out }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+impl<W: Write> Tracer for LogTracer<W> {
+    fn instr(&mut self, pc: usize, instr: &Instr, comp: &IntComp) {
+        let mut line = format!("{:04}: {}", pc, instr);
+        let vals: Vec<_> = sources(instr)
+            .into_iter()
+            .filter(|arg| !matches!(arg, Arg::Imm(..)))
+            .filter_map(|arg| arg.load(&comp.mem).ok())
+            .map(|val| val.to_string())
+            .collect();
+        if !vals.is_empty() {
+            let _ = write!(&mut line, "  ; {}", vals.join(", "));
+        }
+        // tracing is best-effort; a full disk shouldn't stop the program.
+        let _ = writeln!(self.out, "{}", line);
+    }
+    fn input(&mut self, val: isize) {
+        let _ = writeln!(self.out, "      <- {}", val);
+    }
+    fn output(&mut self, val: isize) {
+        let _ = writeln!(self.out, "      -> {}", val);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IoKind {
+    Input,
+    Output,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct IoEvent {
+    pub kind: IoKind,
+    pub val: isize,
+    /// Wall time since the profiler was created.
+    pub at: Duration,
+    /// Instructions executed before the event.
+    pub instr_count: u64,
+}
+
+/// Counts executed instructions per pc and per opcode, and timestamps I/O.
+/// A run ends each time a `hlt` executes, so cloning one machine many times
+/// and tracing each clone with the same profiler yields per-run totals.
+pub struct Profiler {
+    pub pc_counts: HashMap<usize, u64>,
+    pub op_counts: BTreeMap<&'static str, u64>,
+    pub total: u64,
+    pub run_totals: Vec<u64>,
+    pub io_events: Vec<IoEvent>,
+    start: Instant,
+    run_start: u64,
+}
+
+impl Default for Profiler {
+    fn default() -> Self {
+        Profiler::new()
+    }
+}
+
+impl Profiler {
+    pub fn new() -> Profiler {
+        Profiler {
+            pc_counts: HashMap::new(),
+            op_counts: BTreeMap::new(),
+            total: 0,
+            run_totals: Vec::new(),
+            io_events: Vec::new(),
+            start: Instant::now(),
+            run_start: 0,
+        }
+    }
+
+    /// Closes the current run early, e.g. when a machine is abandoned while
+    /// waiting for input.
+    pub fn end_run(&mut self) {
+        if self.total > self.run_start {
+            self.run_totals.push(self.total - self.run_start);
+            self.run_start = self.total;
+        }
+    }
+
+    fn io(&mut self, kind: IoKind, val: isize) {
+        self.io_events.push(IoEvent {
+            kind,
+            val,
+            at: self.start.elapsed(),
+            instr_count: self.total,
+        });
+    }
+
+    /// Summarizes the counts, listing the `top` hottest pcs.
+    pub fn report(&self, top: usize) -> String {
+        let mut out = String::new();
+        let _ = writeln!(&mut out, "instructions: {}", self.total);
+        let mut runs = self.run_totals.clone();
+        if self.total > self.run_start {
+            runs.push(self.total - self.run_start);
+        }
+        if let (Some(min), Some(max)) = (runs.iter().min(), runs.iter().max()) {
+            let _ = writeln!(
+                &mut out,
+                "runs: {} (min {}, max {}, mean {})",
+                runs.len(),
+                min,
+                max,
+                runs.iter().sum::<u64>() / runs.len() as u64
+            );
+        }
+        let pct = |count: u64| 100.0 * count as f64 / self.total.max(1) as f64;
+        let _ = writeln!(&mut out, "by opcode:");
+        let mut ops: Vec<_> = self.op_counts.iter().collect();
+        ops.sort_by(|a, b| b.1.cmp(a.1));
+        for (op, count) in ops {
+            let _ = writeln!(&mut out, "  {:<4} {:>12} {:>6.2}%", op, count, pct(*count));
+        }
+        let _ = writeln!(&mut out, "hot pcs:");
+        let mut pcs: Vec<_> = self.pc_counts.iter().collect();
+        pcs.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+        for (pc, count) in pcs.into_iter().take(top) {
+            let _ = writeln!(&mut out, "  {:04} {:>12} {:>6.2}%", pc, count, pct(*count));
+        }
+        let inputs = self.io_events.iter().filter(|e| e.kind == IoKind::Input);
+        let outputs = self.io_events.iter().filter(|e| e.kind == IoKind::Output);
+        let _ = write!(
+            &mut out,
+            "io: {} inputs, {} outputs",
+            inputs.count(),
+            outputs.count()
+        );
+        if let (Some(first), Some(last)) = (self.io_events.first(), self.io_events.last()) {
+            let _ = write!(&mut out, ", from {:?} to {:?}", first.at, last.at);
+        }
+        let _ = writeln!(&mut out);
+        out
+    }
+}
+
+impl Tracer for Profiler {
+    fn instr(&mut self, pc: usize, instr: &Instr, _comp: &IntComp) {
+        *self.pc_counts.entry(pc).or_insert(0) += 1;
+        *self.op_counts.entry(instr.mnemonic()).or_insert(0) += 1;
+        self.total += 1;
+        if let Instr::Halt = instr {
+            self.end_run();
+        }
+    }
+    fn input(&mut self, val: isize) {
+        self.io(IoKind::Input, val);
+    }
+    fn output(&mut self, val: isize) {
+        self.io(IoKind::Output, val);
+    }
+}
+
+#[test]
+fn test_trace_and_profile() {
+    // doubles each input until it reads a zero.
+    let mem = crate::asm::assemble(
+        "loop:  in -> [x]
+                jf [x], #end
+                mul [x], #2 -> [x]
+                out [x]
+                jt #1, #loop
+         end:   hlt
+         x:     data 0",
+    )
+    .unwrap();
+    let mut comp = IntComp::new(mem, &[3, 0]);
+    let mut tracer = (LogTracer::new(Vec::new()), Profiler::new());
+    while let crate::State::Run = comp.step_with(&mut tracer).unwrap() {}
+    let (log, profile) = tracer;
+    assert_eq!(
+        String::from_utf8(log.into_inner()).unwrap(),
+        "0000: IN -> [15]
+      <- 3
+0002: JF [15], #14  ; 3
+0005: MUL [15], #2 -> [15]  ; 3
+0009: OUT [15]  ; 6
+      -> 6
+0011: JT #1, #0
+0000: IN -> [15]
+      <- 0
+0002: JF [15], #14  ; 0
+0014: HLT
+"
+    );
+    assert_eq!(profile.total, 8);
+    assert_eq!(profile.run_totals, vec![8]);
+    assert_eq!(profile.pc_counts[&0], 2);
+    assert_eq!(profile.op_counts["jf"], 2);
+    let kinds: Vec<_> = profile.io_events.iter().map(|e| (e.kind, e.val)).collect();
+    assert_eq!(
+        kinds,
+        vec![(IoKind::Input, 3), (IoKind::Output, 6), (IoKind::Input, 0)]
+    );
+}